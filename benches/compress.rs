@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use lz77::CompressOptions;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+/// How much input each synthetic benchmark compresses.
+const SYNTHETIC_LEN: usize = 1 << 20;
+
+/// Incompressible xorshift noise, exercising the match finder with no useful matches.
+fn noise() -> Vec<u8> {
+    common::noise(0x2545_F491, SYNTHETIC_LEN)
+}
+
+fn compress(c: &mut Criterion) {
+    let inputs = [
+        ("000", std::fs::read("tests/data/000.decompressed").unwrap()),
+        ("noise", noise()),
+        ("text", common::text(0x2545_F491, SYNTHETIC_LEN)),
+        ("zeros", vec![0; SYNTHETIC_LEN]),
+    ];
+
+    let mut group = c.benchmark_group("compress");
+    for (name, input) in &inputs {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+
+        for level in [1, 6, 9] {
+            let options = CompressOptions::level(level);
+            group.bench_with_input(
+                BenchmarkId::new(format!("level{}", level), name),
+                input,
+                |b, input| {
+                    b.iter(|| {
+                        let mut out = Vec::with_capacity(input.len());
+                        lz77::compress_with_options(black_box(input), &mut out, options).unwrap();
+                        out
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compress);
+criterion_main!(benches);
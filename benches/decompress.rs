@@ -0,0 +1,105 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+/// Roughly how much decompressed output each synthetic stream produces.
+const SYNTHETIC_LEN: usize = 1 << 20;
+
+/// A stream made only of maximum-length literal tokens.
+fn all_literal() -> Vec<u8> {
+    let mut stream = Vec::new();
+    for i in 0..SYNTHETIC_LEN / 32 {
+        stream.push(0x1F);
+        stream.extend((0..32).map(|j| (i + j) as u8));
+    }
+    stream
+}
+
+/// A single literal byte followed by maximum-length runs copying it.
+fn all_dictionary_rle() -> Vec<u8> {
+    let mut stream = vec![0x00, 0xAA];
+    for _ in 0..SYNTHETIC_LEN / 264 {
+        stream.extend_from_slice(&[0xE0, 0xFF, 0x00]);
+    }
+    stream
+}
+
+/// 256 literal bytes followed by 200 byte copies from 256 back, which never overlap their output.
+fn all_dictionary_copies() -> Vec<u8> {
+    let mut stream = Vec::new();
+    for i in 0..8 {
+        stream.push(0x1F);
+        stream.extend((0..32).map(|j| (i * 32 + j) as u8));
+    }
+    for _ in 0..SYNTHETIC_LEN / 200 {
+        stream.extend_from_slice(&[0xE0, 200 - 9, 0xFF]);
+    }
+    stream
+}
+
+/// Text-like data with a mix of literals and matches, encoded by the crate's own compressor.
+fn mixed() -> Vec<u8> {
+    let data = common::text(0x2545_F491, SYNTHETIC_LEN);
+
+    let mut stream = Vec::new();
+    lz77::compress(&data, &mut stream).unwrap();
+    stream
+}
+
+fn decompress(c: &mut Criterion) {
+    let streams = [
+        ("000", std::fs::read("tests/data/000.compressed").unwrap()),
+        ("all_literal", all_literal()),
+        ("all_dictionary_rle", all_dictionary_rle()),
+        ("all_dictionary_copies", all_dictionary_copies()),
+        ("mixed", mixed()),
+    ];
+
+    let mut group = c.benchmark_group("decompress");
+    for (name, input) in &streams {
+        let output_len = lz77::decompress_slice(input).unwrap().len();
+        group.throughput(Throughput::Bytes(output_len as u64));
+
+        group.bench_with_input(BenchmarkId::new("read", name), input, |b, input| {
+            b.iter(|| lz77::decompress(Cursor::new(black_box(input))).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("slice", name), input, |b, input| {
+            b.iter(|| lz77::decompress_slice(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Growing the output as tokens are decoded, against reserving all of it up front.
+fn growth(c: &mut Criterion) {
+    let streams = [("all_literal", all_literal()), ("mixed", mixed())];
+
+    let mut group = c.benchmark_group("growth");
+    for (name, input) in &streams {
+        let output_len = lz77::decompress_slice(input).unwrap().len();
+        group.throughput(Throughput::Bytes(output_len as u64));
+
+        group.bench_with_input(BenchmarkId::new("grow", name), input, |b, input| {
+            b.iter(|| {
+                lz77::Decompress::new()
+                    .from_slice(black_box(input))
+                    .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("reserved", name), input, |b, input| {
+            b.iter(|| {
+                lz77::Decompress::new()
+                    .capacity(output_len)
+                    .from_slice(black_box(input))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decompress, growth);
+criterion_main!(benches);
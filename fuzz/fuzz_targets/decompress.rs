@@ -0,0 +1,18 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Any input must decode or fail cleanly, never panic or hang, and the slice and reader paths
+    // must agree on the result.
+    let from_slice = lz77::decompress_slice(data);
+    let from_reader = lz77::decompress(Cursor::new(data));
+
+    match (from_slice, from_reader) {
+        (Ok(a), Ok(b)) => assert_eq!(a, b),
+        (Err(_), Err(_)) => {}
+        (a, b) => panic!("slice and reader disagree: {:?} vs {:?}", a, b),
+    }
+});
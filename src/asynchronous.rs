@@ -0,0 +1,100 @@
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+
+use crate::decompress::{apply_token_at, follow_len, parse_token};
+use crate::source::SliceSource;
+use crate::{DecompressError, Offset, MAX_LITERAL_LEN};
+
+/// Decompress a data stream from an async reader.
+///
+/// Behaves like `decompress()`, awaiting the reader for each token instead of blocking on it. The
+/// reader is buffered internally and read through to the end. Requires the `tokio` feature.
+pub async fn decompress_async<R: AsyncRead + Unpin>(reader: R) -> Result<Vec<u8>, DecompressError> {
+    let mut reader = BufReader::new(reader);
+    let mut dictionary = Vec::new();
+    let mut input_pos = 0;
+    let mut literal = [0u8; MAX_LITERAL_LEN];
+
+    loop {
+        let mut cb = 0;
+        if reader.read(core::slice::from_mut(&mut cb)).await? == 0 {
+            return Ok(dictionary);
+        }
+
+        let mut follow = [0u8; 2];
+        let follow = &mut follow[..follow_len(cb)];
+        read_full(&mut reader, follow).await?;
+        let offset = parse_token(cb, follow)?;
+
+        // Literal data is read up front, so the shared token logic only ever sees it in memory.
+        let data = match offset {
+            Offset::Literal { length } => {
+                read_full(&mut reader, &mut literal[..length]).await?;
+                &literal[..length]
+            }
+            _ => &[],
+        };
+        apply_token_at(
+            &mut SliceSource::new(data),
+            &mut dictionary,
+            offset,
+            input_pos,
+        )?;
+
+        input_pos += 1 + follow.len() + data.len();
+    }
+}
+
+/// Fill `buf` from the reader, reporting how much was read if it ends first.
+async fn read_full<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> Result<(), DecompressError> {
+    let mut got = 0;
+    while got < buf.len() {
+        match reader.read(&mut buf[got..]).await? {
+            0 => {
+                return Err(DecompressError::UnexpectedEof {
+                    expected: buf.len(),
+                    got,
+                })
+            }
+            n => got += n,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decompress_async() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        assert_eq!(
+            decompress_async(&input[..]).await?,
+            std::fs::read("tests/data/000.decompressed")?
+        );
+        assert!(decompress_async(&[][..]).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decompress_async_errors() {
+        assert!(matches!(
+            decompress_async(&[0x02, 0x01][..]).await,
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        assert!(matches!(
+            decompress_async(&[0x02, b'a', b'b', b'c', 0x20, 0x00, 0x20, 0x10][..]).await,
+            Err(DecompressError::OffsetTooLarge {
+                offset: 17,
+                dictionary_len: 6,
+                input_pos: 6
+            })
+        ));
+    }
+}
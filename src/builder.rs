@@ -0,0 +1,729 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{BufRead, Read, Seek, SeekFrom};
+
+#[cfg(feature = "std")]
+use crate::decompress::buffered;
+use crate::decompress::{decode, DecodeOptions};
+use crate::recover::{decode_recovering, Recovered};
+#[cfg(feature = "std")]
+use crate::source::BufSource;
+use crate::source::{ByteSource, IterSource, SliceSource};
+use crate::DecompressError;
+
+/// Builder combining the options of the `decompress_with_*` functions.
+///
+/// ```
+/// # fn main() -> Result<(), lz77::DecompressError> {
+/// let stream = [0x80, 0x05, 0x02, b'y', b'o', b'u'];
+/// let output = lz77::Decompress::new()
+///     .preset_dictionary(b"say hello ")
+///     .max_output(1024)
+///     .from_slice(&stream)?;
+/// assert_eq!(output, b"hello you");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decompress<'a> {
+    max_output: usize,
+    max_input: usize,
+    start_at: u64,
+    preset: &'a [u8],
+    capacity: usize,
+    strict_eof: bool,
+    canonical: bool,
+    trace: bool,
+    forbid_literals: bool,
+    recover: bool,
+}
+
+impl Default for Decompress<'_> {
+    fn default() -> Self {
+        Decompress {
+            max_output: usize::MAX,
+            max_input: usize::MAX,
+            start_at: 0,
+            preset: &[],
+            capacity: 0,
+            strict_eof: false,
+            canonical: false,
+            trace: false,
+            forbid_literals: false,
+            recover: false,
+        }
+    }
+}
+
+impl<'a> Decompress<'a> {
+    /// Options matching `decompress()`: no limit, no preset dictionary and no reserved capacity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse to produce more than `max_output` bytes, see `decompress_with_limit()`.
+    ///
+    /// The preset dictionary does not count towards the limit.
+    pub fn max_output(mut self, max_output: usize) -> Self {
+        self.max_output = max_output;
+        self
+    }
+
+    /// Refuse to read more than `max_input` bytes of compressed input, failing with
+    /// `DecompressError::InputLimitExceeded`.
+    ///
+    /// Each part of a token is checked before it is read, so a token ending past the limit fails
+    /// without its follow-up bytes or literal data being read, while a stream of exactly
+    /// `max_input` bytes decodes. To tell whether a stream ends at the limit, one byte past it is
+    /// read. Meant for untrusted sources, such as a peer sending an endless stream of literals:
+    /// where `max_output()` bounds the memory used, this bounds the input consumed.
+    pub fn max_input(mut self, max_input: usize) -> Self {
+        self.max_input = max_input;
+        self
+    }
+
+    /// Begin parsing `start_at` bytes into the input, which must be the start of a token, such as
+    /// a block boundary recorded in an index.
+    ///
+    /// Only `read_seekable()` supports this, seeking straight there; every other method fails with
+    /// `DecompressError::UnsupportedOption` rather than reading and discarding the input before it.
+    /// It cannot be combined with `preset_dictionary()` either. Decoding starts with no history, so
+    /// for blocks compressed independently a dictionary token before any output fails with
+    /// `DecompressError::OffsetTooLarge`, catching an offset that does not land on a block. Input
+    /// positions in errors, and the `max_input()` budget, count from `start_at`. Starting past the
+    /// end of the input fails with `DecompressError::UnexpectedEof`.
+    pub fn start_at(mut self, start_at: u64) -> Self {
+        self.start_at = start_at;
+        self
+    }
+
+    /// Start the decoder with `preset` in its dictionary, see `decompress_with_dictionary()`.
+    pub fn preset_dictionary(mut self, preset: &'a [u8]) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Reserve `capacity` bytes for the output up front, see `decompress_with_capacity()`.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Treat an `END_OF_MEMBER` terminator as the end of the stream, and fail with
+    /// `DecompressError::TrailingData` if any input follows it.
+    ///
+    /// Catches streams that were accidentally concatenated, provided the writer terminated them,
+    /// as `compress_with_options()` does with `CompressOptions::end_of_member` set. A stream
+    /// without a terminator still ends cleanly at the end of the input.
+    pub fn strict_eof(mut self, strict_eof: bool) -> Self {
+        self.strict_eof = strict_eof;
+        self
+    }
+
+    /// Reject any stream whose tokens are not split the way `compress()` splits them, with
+    /// `DecompressError::NonCanonical`.
+    ///
+    /// Each length and offset has only one encoding as a single token, but a literal run or a match
+    /// can be split into several tokens in more than one way, so different streams can decode to
+    /// the same output. With this set, the stream must use the encoder's split: whole literal
+    /// tokens before a shorter last one, and the same for the tokens of a match. Useful where the
+    /// compressed bytes themselves are hashed, as in content-addressed storage. Which matches the
+    /// encoder chose is not checked.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// On failure, wrap the error in `DecompressError::Traced` along with the last `TRACE_LEN`
+    /// tokens applied before it, their input positions and the output length after each.
+    ///
+    /// Shows how a malformed stream got into the state that broke it, such as the tokens leading up
+    /// to an offset reaching past the start of the output. Keeping the trace costs a little on
+    /// every token, so it is meant for debugging rather than left on.
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Reject any stream containing a literal token, with `DecompressError::UnexpectedLiteral`.
+    ///
+    /// For protocols where a message must be built entirely from dictionary references, usually
+    /// into a `preset_dictionary()`, this enforces that at decode time.
+    pub fn forbid_literals(mut self, forbid_literals: bool) -> Self {
+        self.forbid_literals = forbid_literals;
+        self
+    }
+
+    /// Skip over corrupt input instead of failing, when decoding with `from_slice_recovering()`.
+    ///
+    /// Only `from_slice_recovering()` can recover; every other method fails with
+    /// `DecompressError::UnsupportedOption` while this is set.
+    ///
+    /// Wherever a token fails to decode, it is dropped, the input is advanced by one byte, and
+    /// decoding resumes as if a token started there. This is lossy: the output around a skipped run
+    /// is missing bytes, and everything copied from it afterwards is shifted or wrong, but most of a
+    /// stream with a few damaged bytes usually survives. Meant for salvaging damaged archives.
+    ///
+    /// Exceeding `max_output()` still fails. The `max_input()`, `strict_eof()`, `canonical()`,
+    /// `forbid_literals()` and `trace()` checks do not apply while recovering.
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Decompress a data stream from the reader, buffering it internally.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(&self, reader: R) -> Result<Vec<u8>, DecompressError> {
+        self.decode(buffered(reader))
+    }
+
+    /// Decompress a data stream from a seekable reader, seeking to `start_at()` first.
+    #[cfg(feature = "std")]
+    pub fn read_seekable<R: Read + Seek>(&self, mut reader: R) -> Result<Vec<u8>, DecompressError> {
+        if self.start_at != 0 && !self.preset.is_empty() {
+            return Err(DecompressError::UnsupportedOption {
+                option: "start_at with preset_dictionary",
+            });
+        }
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        if self.start_at > end {
+            return Err(DecompressError::UnexpectedEof {
+                expected: usize::try_from(self.start_at).unwrap_or(usize::MAX),
+                got: usize::try_from(end).unwrap_or(usize::MAX),
+            });
+        }
+        reader.seek(SeekFrom::Start(self.start_at))?;
+        self.decode_here(buffered(reader))
+    }
+
+    /// Decompress a data stream from a reader that is already buffered, see
+    /// `decompress_buffered()`.
+    #[cfg(feature = "std")]
+    pub fn read_buffered<R: BufRead>(&self, reader: R) -> Result<Vec<u8>, DecompressError> {
+        self.decode(BufSource::new(reader))
+    }
+
+    /// Decompress an in-memory data stream, see `decompress_slice()`.
+    pub fn from_slice(&self, input: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        self.decode(SliceSource::new(input))
+    }
+
+    /// Decompress a data stream from an iterator of bytes, see `decompress_iter()`.
+    pub fn read_iter<I: IntoIterator<Item = u8>>(
+        &self,
+        iter: I,
+    ) -> Result<Vec<u8>, DecompressError> {
+        self.decode(IterSource::new(iter.into_iter()))
+    }
+
+    /// Decompress a data stream from a custom `ByteSource`.
+    pub fn read_source<S: ByteSource>(&self, source: S) -> Result<Vec<u8>, DecompressError> {
+        self.decode(source)
+    }
+
+    /// Decompress an in-memory data stream, reporting the input skipped with `recover()`.
+    ///
+    /// Without `recover()` set this behaves like `from_slice()`, and never skips anything.
+    pub fn from_slice_recovering(&self, input: &[u8]) -> Result<Recovered, DecompressError> {
+        if !self.recover {
+            return self.from_slice(input).map(|output| Recovered {
+                output,
+                skipped: Vec::new(),
+            });
+        }
+        if self.start_at != 0 {
+            return Err(DecompressError::UnsupportedOption { option: "start_at" });
+        }
+
+        let mut dictionary = Vec::with_capacity(self.preset.len().saturating_add(self.capacity));
+        dictionary.extend_from_slice(self.preset);
+
+        let limit = self.max_output.saturating_add(self.preset.len());
+        let skipped = decode_recovering(input, &mut dictionary, 0, limit)
+            .map_err(|err| self.map_error(err))?;
+
+        dictionary.drain(..self.preset.len());
+        Ok(Recovered {
+            output: dictionary,
+            skipped,
+        })
+    }
+
+    /// Decode the stream from the start of `source`, which cannot skip to `start_at()`.
+    fn decode<S: ByteSource>(&self, source: S) -> Result<Vec<u8>, DecompressError> {
+        if self.start_at != 0 {
+            return Err(DecompressError::UnsupportedOption { option: "start_at" });
+        }
+        self.decode_here(source)
+    }
+
+    /// Decode the stream from the current position of `source`.
+    fn decode_here<S: ByteSource>(&self, source: S) -> Result<Vec<u8>, DecompressError> {
+        if self.recover {
+            return Err(DecompressError::UnsupportedOption { option: "recover" });
+        }
+
+        let mut dictionary = Vec::with_capacity(self.preset.len().saturating_add(self.capacity));
+        dictionary.extend_from_slice(self.preset);
+
+        let options = DecodeOptions {
+            max_output: self.max_output.saturating_add(self.preset.len()),
+            max_input: self.max_input,
+            strict_eof: self.strict_eof,
+            canonical: self.canonical,
+            trace: self.trace,
+            forbid_literals: self.forbid_literals,
+            ..DecodeOptions::default()
+        };
+        decode(source, &mut dictionary, 0, options).map_err(|err| self.map_error(err))?;
+
+        dictionary.drain(..self.preset.len());
+        Ok(dictionary)
+    }
+
+    /// Report output limits and lengths without the preset dictionary, as the caller sees them.
+    fn map_error(&self, err: DecompressError) -> DecompressError {
+        match err {
+            DecompressError::OutputLimitExceeded { .. } => DecompressError::OutputLimitExceeded {
+                limit: self.max_output,
+            },
+            DecompressError::Traced { error, mut trace } => {
+                for entry in &mut trace {
+                    entry.output_len -= self.preset.len();
+                }
+                DecompressError::Traced {
+                    error: Box::new(self.map_error(*error)),
+                    trace,
+                }
+            }
+            err => err,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_decompress_builder() -> Result<(), Box<dyn std::error::Error>> {
+        let compressed = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        assert_eq!(
+            Decompress::new().read_from(Cursor::new(&compressed))?,
+            expected
+        );
+        assert_eq!(
+            Decompress::new()
+                .capacity(expected.len())
+                .max_output(expected.len())
+                .read_buffered(&compressed[..])?,
+            expected
+        );
+        assert_eq!(Decompress::new().from_slice(&compressed)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_strict_eof() -> Result<(), DecompressError> {
+        let mut stream = vec![0x02, b'a', b'b', b'c'];
+        stream.extend_from_slice(&crate::END_OF_MEMBER);
+
+        let strict = Decompress::new().strict_eof(true);
+        assert_eq!(strict.from_slice(&stream)?, b"abc".to_vec());
+        assert_eq!(strict.from_slice(&stream[..4])?, b"abc".to_vec());
+
+        stream.extend_from_slice(&[0x00, b'd']);
+        assert!(matches!(
+            strict.read_from(Cursor::new(&stream)),
+            Err(DecompressError::TrailingData)
+        ));
+        assert!(matches!(
+            Decompress::new().from_slice(&stream),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_strict_eof_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let data = std::fs::read("tests/data/000.decompressed")?;
+        let options = crate::CompressOptions {
+            end_of_member: true,
+            ..crate::CompressOptions::default()
+        };
+        let mut stream = Vec::new();
+        crate::compress_with_options(&data, &mut stream, options)?;
+        assert!(stream.ends_with(&crate::END_OF_MEMBER));
+
+        let strict = Decompress::new().strict_eof(true);
+        assert_eq!(strict.from_slice(&stream)?, data);
+
+        // Two streams concatenated by mistake.
+        let mut concatenated = stream.clone();
+        crate::compress_with_options(b"more", &mut concatenated, options)?;
+        assert!(matches!(
+            strict.from_slice(&concatenated),
+            Err(DecompressError::TrailingData)
+        ));
+        assert_eq!(
+            crate::decompress_members(Cursor::new(&concatenated))?,
+            [data, b"more".to_vec()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_canonical() -> Result<(), Box<dyn std::error::Error>> {
+        let canonical = Decompress::new().canonical(true);
+
+        let data = std::fs::read("tests/data/000.decompressed")?;
+        for level in [1, 6, 9] {
+            let mut compressed = Vec::new();
+            crate::compress_with_options(
+                &data,
+                &mut compressed,
+                crate::CompressOptions::level(level),
+            )?;
+            assert_eq!(canonical.from_slice(&compressed)?, data);
+        }
+
+        // "abcabcab" as a literal and one 5 byte copy, then "abcabcabc" with its copy split in two.
+        assert_eq!(
+            canonical.from_slice(&[0x02, b'a', b'b', b'c', 0x60, 0x02])?,
+            b"abcabcab".to_vec()
+        );
+        assert!(matches!(
+            canonical.from_slice(&[0x02, b'a', b'b', b'c', 0x20, 0x02, 0x20, 0x02]),
+            Err(DecompressError::NonCanonical { input_pos: 6 })
+        ));
+        assert!(matches!(
+            canonical.from_slice(&[0x00, b'a', 0x00, b'b']),
+            Err(DecompressError::NonCanonical { input_pos: 2 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_trace() {
+        use crate::{Offset, TraceEntry};
+
+        // "hello " from the preset, a literal "you", then a copy from before the preset.
+        let stream = [0x80, 0x05, 0x02, b'y', b'o', b'u', 0x20, 0x40];
+        let builder = Decompress::new().preset_dictionary(b"say hello ");
+        assert!(matches!(
+            builder.from_slice(&stream),
+            Err(DecompressError::OffsetTooLarge { input_pos: 6, .. })
+        ));
+
+        let Err(DecompressError::Traced { error, trace }) = builder.trace(true).from_slice(&stream)
+        else {
+            panic!("expected a traced error");
+        };
+        assert!(matches!(
+            *error,
+            DecompressError::OffsetTooLarge {
+                offset: 65,
+                dictionary_len: 19,
+                input_pos: 6
+            }
+        ));
+        assert_eq!(
+            trace,
+            [
+                TraceEntry {
+                    input_pos: 0,
+                    offset: Offset::Dictionary {
+                        length: 6,
+                        offset: 6
+                    },
+                    output_len: 6
+                },
+                TraceEntry {
+                    input_pos: 2,
+                    offset: Offset::Literal { length: 3 },
+                    output_len: 9
+                },
+            ]
+        );
+
+        assert!(matches!(
+            builder.trace(true).max_output(8).from_slice(&stream),
+            Err(DecompressError::Traced { error, .. })
+                if matches!(*error, DecompressError::OutputLimitExceeded { limit: 8 })
+        ));
+        assert_eq!(
+            builder.trace(true).from_slice(&stream[..6]).unwrap(),
+            b"hello you".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_decompress_builder_forbid_literals() -> Result<(), DecompressError> {
+        let builder = Decompress::new()
+            .preset_dictionary(b"say hello ")
+            .forbid_literals(true);
+
+        // "hello " and then "hello" again, both copied from the preset.
+        assert_eq!(
+            builder.from_slice(&[0x80, 0x05, 0x60, 0x0B])?,
+            b"hello hello".to_vec()
+        );
+        assert!(matches!(
+            builder.from_slice(&[0x80, 0x05, 0x02, b'y', b'o', b'u']),
+            Err(DecompressError::UnexpectedLiteral { input_pos: 2 })
+        ));
+        assert_eq!(
+            builder
+                .forbid_literals(false)
+                .from_slice(&[0x80, 0x05, 0x02, b'y', b'o', b'u'])?,
+            b"hello you".to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_max_input() -> Result<(), Box<dyn std::error::Error>> {
+        let compressed = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        assert_eq!(
+            Decompress::new()
+                .max_input(compressed.len())
+                .read_from(Cursor::new(&compressed))?,
+            expected
+        );
+        assert!(matches!(
+            Decompress::new()
+                .max_input(compressed.len() - 1)
+                .read_from(Cursor::new(&compressed)),
+            Err(DecompressError::InputLimitExceeded { limit }) if limit == compressed.len() - 1
+        ));
+
+        // An endless run of 33-byte literal tokens. Three fit in the budget; the fourth token's
+        // control byte is read, but none of its data.
+        let mut read = 0;
+        let endless = core::iter::repeat(0x1F).inspect(|_| read += 1);
+        assert!(matches!(
+            Decompress::new().max_input(100).read_iter(endless),
+            Err(DecompressError::InputLimitExceeded { limit: 100 })
+        ));
+        assert_eq!(read, 100);
+
+        // "abc" and then a long copy, with the limit inside the copy's follow-up bytes.
+        let stream = crate::compress::TokenWriter::new()
+            .literal(b"abc")
+            .dict(20, 3)
+            .finish();
+        assert_eq!(stream.len(), 7);
+        for max_input in [5, 6] {
+            let mut read = 0;
+            let counted = stream.iter().copied().inspect(|_| read += 1);
+            assert!(matches!(
+                Decompress::new().max_input(max_input).read_iter(counted),
+                Err(DecompressError::InputLimitExceeded { limit }) if limit == max_input
+            ));
+            assert_eq!(read, 5, "limit {}", max_input);
+        }
+        assert_eq!(
+            Decompress::new().max_input(7).from_slice(&stream)?.len(),
+            23
+        );
+        Ok(())
+    }
+
+    /// A source handing out bytes from a fixed stream, as a caller might implement one.
+    struct Chunks<'a>(&'a [u8]);
+
+    impl ByteSource for Chunks<'_> {
+        fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+            let (&byte, rest) = match self.0.split_first() {
+                Some(split) => split,
+                None => return Ok(None),
+            };
+            self.0 = rest;
+            Ok(Some(byte))
+        }
+
+        fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+            if buf.len() > self.0.len() {
+                return Err(DecompressError::UnexpectedEof {
+                    expected: buf.len(),
+                    got: self.0.len(),
+                });
+            }
+            let (head, rest) = self.0.split_at(buf.len());
+            buf.copy_from_slice(head);
+            self.0 = rest;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_decompress_builder_read_source() -> Result<(), Box<dyn std::error::Error>> {
+        let compressed = std::fs::read("tests/data/000.compressed")?;
+        assert_eq!(
+            Decompress::new().read_source(Chunks(&compressed))?,
+            std::fs::read("tests/data/000.decompressed")?
+        );
+        assert!(matches!(
+            Decompress::new().read_source(Chunks(&[0x02, b'a'])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_start_at() -> Result<(), Box<dyn std::error::Error>> {
+        // Two independently compressed blocks, with an index of where each starts.
+        let blocks: [&[u8]; 2] = [
+            b"the first block, the first block",
+            b"and then a second one",
+        ];
+        let mut stream = Vec::new();
+        let mut index = Vec::new();
+        for block in blocks {
+            index.push(stream.len() as u64);
+            crate::compress(block, &mut stream)?;
+        }
+
+        let second = Decompress::new().start_at(index[1]);
+        assert_eq!(second.read_seekable(Cursor::new(&stream))?, blocks[1]);
+        assert!(matches!(
+            second.read_from(Cursor::new(&stream)),
+            Err(DecompressError::UnsupportedOption { option: "start_at" })
+        ));
+        assert!(matches!(
+            second.from_slice(&stream),
+            Err(DecompressError::UnsupportedOption { option: "start_at" })
+        ));
+        assert!(matches!(
+            second.recover(true).from_slice_recovering(&stream),
+            Err(DecompressError::UnsupportedOption { option: "start_at" })
+        ));
+        assert!(matches!(
+            second
+                .preset_dictionary(blocks[0])
+                .read_seekable(Cursor::new(&stream)),
+            Err(DecompressError::UnsupportedOption {
+                option: "start_at with preset_dictionary"
+            })
+        ));
+        assert_eq!(
+            Decompress::new()
+                .start_at(index[0])
+                .read_seekable(Cursor::new(&stream))?,
+            blocks.concat()
+        );
+        assert!(Decompress::new()
+            .start_at(stream.len() as u64)
+            .read_seekable(Cursor::new(&stream))?
+            .is_empty());
+        let past_end = stream.len() + 10;
+        assert!(matches!(
+            Decompress::new()
+                .start_at(past_end as u64)
+                .read_seekable(Cursor::new(&stream)),
+            Err(DecompressError::UnexpectedEof { expected, got })
+                if expected == past_end && got == stream.len()
+        ));
+
+        // An offset landing on a dictionary token rather than a block boundary.
+        let stream = crate::compress::TokenWriter::new()
+            .literal(b"abc")
+            .dict(3, 3)
+            .finish();
+        assert!(matches!(
+            Decompress::new()
+                .start_at(4)
+                .read_seekable(Cursor::new(&stream)),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 0,
+                input_pos: 0
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_recover() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        let mut compressed = Vec::new();
+        crate::compress(&expected, &mut compressed)?;
+        let recovering = Decompress::new().recover(true);
+
+        let clean = recovering.from_slice_recovering(&compressed)?;
+        assert_eq!(clean.output, expected);
+        assert!(clean.skipped.is_empty());
+
+        // No other method recovers, so none quietly ignores the option.
+        assert!(matches!(
+            recovering.from_slice(&compressed),
+            Err(DecompressError::UnsupportedOption { option: "recover" })
+        ));
+        assert!(matches!(
+            recovering.read_from(Cursor::new(&compressed)),
+            Err(DecompressError::UnsupportedOption { option: "recover" })
+        ));
+
+        // Point a dictionary token half way through at far beyond the start of the output.
+        let mut at = 0;
+        for token in crate::tokens(Cursor::new(&compressed)) {
+            let token = token?;
+            if at >= compressed.len() / 2 && matches!(token, crate::Offset::Dictionary { .. }) {
+                break;
+            }
+            at += token.encoded_len();
+            if let crate::Offset::Literal { length } = token {
+                at += length;
+            }
+        }
+        compressed[at] |= 0b1_1111;
+        assert!(Decompress::new().from_slice(&compressed).is_err());
+        assert!(Decompress::new()
+            .from_slice_recovering(&compressed)
+            .is_err());
+
+        let recovered = recovering.from_slice_recovering(&compressed)?;
+        assert!(!recovered.skipped.is_empty());
+        assert_eq!(recovered.skipped[0].range.start, at);
+        assert!(recovered.output.len() * 10 >= expected.len() * 9);
+        let intact = recovered
+            .output
+            .iter()
+            .zip(&expected)
+            .take_while(|(a, b)| a == b)
+            .count();
+        assert!(intact * 3 >= expected.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_builder_limit_and_dictionary() -> Result<(), DecompressError> {
+        // "hello " copied from the preset, then a literal "you", then "hello" again.
+        let stream = [0x80, 0x05, 0x02, b'y', b'o', b'u', 0x60, 0x08];
+        let builder = Decompress::new().preset_dictionary(b"say hello ");
+
+        assert_eq!(
+            builder.max_output(14).read_from(Cursor::new(stream))?,
+            b"hello youhello".to_vec()
+        );
+        assert!(matches!(
+            builder.max_output(13).read_from(Cursor::new(stream)),
+            Err(DecompressError::OutputLimitExceeded { limit: 13 })
+        ));
+        assert!(matches!(
+            Decompress::new().max_output(14).from_slice(&stream),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{DecompressError, Offset, MAX_LITERAL_LEN, MAX_MATCH_LEN, MAX_OFFSET, MIN_MATCH_LEN};
+
+/// Checks that tokens are split the way `compress()` splits them, see `Decompress::canonical()`.
+///
+/// Every length and offset has exactly one encoding as a single token, so the only freedom the
+/// format leaves is in splitting a run into several tokens. The encoder only ever splits:
+///
+/// - a literal run into full `MAX_LITERAL_LEN` tokens followed by the remainder, and
+/// - a match into full length tokens followed by the remainder, except that a remainder shorter
+///   than `MIN_MATCH_LEN` is made up by shortening the token before it to leave a final 3 byte one.
+///
+/// A match is always extended as far as it goes, so consecutive dictionary tokens with the same
+/// offset only ever come from splitting one match. Which matches an encoder chooses is not checked.
+#[derive(Debug, Default)]
+pub(crate) struct CanonicalCheck {
+    previous: Option<Offset>,
+    /// The previous token ended a split match with its final short token.
+    match_ended: bool,
+}
+
+impl CanonicalCheck {
+    /// Check the next token of the stream, whose control byte is at `input_pos`.
+    pub(crate) fn check(&mut self, token: Offset, input_pos: usize) -> Result<(), DecompressError> {
+        let mut ends_match = false;
+
+        let canonical = match (self.previous, token) {
+            (_, Offset::Dictionary { length, offset }) if length > max_token(offset) => false,
+            (Some(Offset::Literal { length: previous }), Offset::Literal { .. }) => {
+                previous == MAX_LITERAL_LEN
+            }
+            (
+                Some(Offset::Dictionary {
+                    length: previous,
+                    offset: previous_offset,
+                }),
+                Offset::Dictionary { length, offset },
+            ) if offset == previous_offset => {
+                let max = max_token(offset);
+                ends_match = previous != max;
+                !self.match_ended
+                    && (previous == max
+                        || (previous + MIN_MATCH_LEN > max && length == MIN_MATCH_LEN))
+            }
+            _ => true,
+        };
+
+        if !canonical {
+            return Err(DecompressError::NonCanonical { input_pos });
+        }
+
+        self.previous = Some(token);
+        self.match_ended = ends_match;
+        Ok(())
+    }
+}
+
+/// Longest dictionary token the encoder writes at `offset`.
+///
+/// A full length token at `MAX_OFFSET` is avoided, as it is reserved as `END_OF_MEMBER`.
+fn max_token(offset: usize) -> usize {
+    if offset == MAX_OFFSET {
+        MAX_MATCH_LEN - 1
+    } else {
+        MAX_MATCH_LEN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_all(tokens: &[Offset]) -> Result<(), DecompressError> {
+        let mut check = CanonicalCheck::default();
+        for (i, &token) in tokens.iter().enumerate() {
+            check.check(token, i)?;
+        }
+        Ok(())
+    }
+
+    fn literal(length: usize) -> Offset {
+        Offset::Literal { length }
+    }
+
+    fn copy(length: usize, offset: usize) -> Offset {
+        Offset::Dictionary { length, offset }
+    }
+
+    #[test]
+    fn test_canonical() {
+        assert!(check_all(&[literal(32), literal(32), literal(5), copy(4, 1), literal(1)]).is_ok());
+        assert!(check_all(&[
+            copy(8, 2),
+            copy(8, 3),
+            copy(264, 1),
+            copy(264, 1),
+            copy(9, 1)
+        ])
+        .is_ok());
+        // 266 bytes: 263 and 3, rather than 264 and 2.
+        assert!(check_all(&[copy(263, 1), copy(3, 1), literal(1), copy(3, 1)]).is_ok());
+        assert!(check_all(&[copy(263, MAX_OFFSET), copy(100, MAX_OFFSET)]).is_ok());
+    }
+
+    #[test]
+    fn test_non_canonical() {
+        for (tokens, at) in [
+            (&[literal(31), literal(1)][..], 1),
+            (&[copy(4, 1), copy(4, 1)], 1),
+            (&[copy(264, 1), copy(263, 1), copy(3, 1), copy(3, 1)], 3),
+            (&[copy(262, 1), copy(4, 1)], 1),
+            (&[copy(264, MAX_OFFSET)], 0),
+        ] {
+            assert!(
+                matches!(
+                    check_all(tokens),
+                    Err(DecompressError::NonCanonical { input_pos }) if input_pos == at
+                ),
+                "{:?}",
+                tokens
+            );
+        }
+    }
+}
@@ -0,0 +1,768 @@
+use std::io::Write;
+
+use crate::{END_OF_MEMBER, MAX_LITERAL_LEN, MAX_MATCH_LEN, MAX_OFFSET, MIN_MATCH_LEN};
+
+/// Error type returned by compress() and helper methods.
+type Error = std::io::Error;
+
+/// Tuning parameters for `compress_with_options()`.
+///
+/// Higher effort settings trade compression speed for a smaller output. Use
+/// `CompressOptions::level()` for a preset, or set the fields directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressOptions {
+    /// How many earlier positions the match finder examines when looking for a match.
+    pub max_chain_length: usize,
+    /// Defer a match by one byte if a longer match starts at the next position, as in DEFLATE.
+    pub lazy_matching: bool,
+    /// Shortest match worth emitting as a dictionary reference; shorter ones are left as literals.
+    ///
+    /// Values below the format's `MIN_MATCH_LEN` are treated as `MIN_MATCH_LEN`. Matches longer
+    /// than `MAX_MATCH_LEN` are still split into several tokens, the last of which may be shorter.
+    pub min_match: usize,
+    /// Skip match finding and store the input as literal tokens only, as level `0` does.
+    ///
+    /// Fastest, and the output is always exactly `compress_bound()` bytes, so it suits input that
+    /// is already compressed or too small to be worth searching.
+    pub store_only: bool,
+    /// Terminate the stream with `END_OF_MEMBER`, adding its 3 bytes to the output.
+    ///
+    /// A terminated stream can be checked for trailing data with `Decompress::strict_eof()`, or
+    /// concatenated with others and split apart again with `decompress_members()`. Only those
+    /// understand the terminator; other decoders read it as an ordinary dictionary token.
+    pub end_of_member: bool,
+}
+
+impl CompressOptions {
+    /// Preset options for a compression level from `1` (fastest) to `9` (smallest output), or `0`
+    /// to store the input without compressing it.
+    ///
+    /// Low levels use a short, greedy search; higher levels search further and use lazy matching.
+    /// Levels above `9` are clamped.
+    pub fn level(level: u32) -> Self {
+        let (max_chain_length, lazy_matching) = match level {
+            0 | 1 => (4, false),
+            2 => (8, false),
+            3 => (32, false),
+            4 => (16, true),
+            5 => (32, true),
+            6 => (128, true),
+            7 => (256, true),
+            8 => (1024, true),
+            _ => (MAX_OFFSET, true),
+        };
+
+        CompressOptions {
+            max_chain_length,
+            lazy_matching,
+            min_match: MIN_MATCH_LEN,
+            store_only: level == 0,
+            end_of_member: false,
+        }
+    }
+}
+
+impl Default for CompressOptions {
+    /// The options used by `compress()`, equivalent to level 6.
+    fn default() -> Self {
+        CompressOptions::level(6)
+    }
+}
+
+/// Compress a data stream into the writer.
+///
+/// Encodes `data` with a sliding-window match finder using the default `CompressOptions`,
+/// emitting the same control-byte format that `decompress()` reads back.
+///
+/// Matches are capped at `9 + 255` bytes per token; longer matches are written as several
+/// consecutive dictionary tokens.
+///
+/// The output is never larger than storing the input uncompressed, as `CompressOptions::level(0)`
+/// does, which takes exactly `compress_bound()` bytes. The format has no header in which to flag a
+/// stored stream, and none is needed: a dictionary token never costs more than the literal bytes
+/// it replaces, so the encoder's own output is already never worse and no fallback is taken.
+///
+/// Returns a `Result` with an IO error if there are any issues writing data.
+pub fn compress<W: Write>(data: &[u8], writer: W) -> Result<(), Error> {
+    compress_with_options(data, writer, CompressOptions::default())
+}
+
+/// Compress a data stream into the writer with the given tuning options.
+///
+/// Behaves like `compress()`, but lets the caller trade speed for compression ratio.
+pub fn compress_with_options<W: Write>(
+    data: &[u8],
+    mut writer: W,
+    options: CompressOptions,
+) -> Result<(), Error> {
+    Encoder::new(options).encode(data, &mut writer, true)
+}
+
+/// Match finding and token emission state, carried between calls when input arrives in pieces.
+pub(crate) struct Encoder {
+    options: CompressOptions,
+    finder: MatchFinder,
+    /// Start of the literal run not yet written out.
+    literal_start: usize,
+    /// Next position to find a match at.
+    pos: usize,
+    /// Input length to wait for before retrying a match that ran into the end of the input.
+    ///
+    /// Doubles the input seen past `pos` on each retry, so a long run arriving in small pieces is
+    /// searched a logarithmic rather than linear number of times.
+    resume_len: usize,
+}
+
+impl Encoder {
+    pub(crate) fn new(options: CompressOptions) -> Self {
+        Encoder {
+            options,
+            finder: MatchFinder::new(),
+            literal_start: 0,
+            pos: 0,
+            resume_len: 0,
+        }
+    }
+
+    /// Position of the oldest byte of `data` that later calls to `encode()` may still read.
+    pub(crate) fn oldest_needed(&self) -> usize {
+        self.literal_start.min(self.pos.saturating_sub(MAX_OFFSET))
+    }
+
+    /// Encode `data`, which holds every byte passed so far, continuing from where the last call
+    /// stopped.
+    ///
+    /// Unless `finish` is set, more input may follow, so encoding stops at the first match that
+    /// runs into the end of `data`, as it might grow once more arrives. Whole literal tokens are
+    /// still written out, but a trailing partial one is held back. The tokens written are the same
+    /// however the input is split between calls.
+    pub(crate) fn encode(
+        &mut self,
+        data: &[u8],
+        writer: &mut dyn Write,
+        finish: bool,
+    ) -> Result<(), Error> {
+        let min_match = self.options.min_match.max(MIN_MATCH_LEN);
+        let max_chain_length = self.options.max_chain_length;
+
+        if !finish && data.len() < self.resume_len {
+            return Ok(());
+        }
+
+        if self.options.store_only {
+            self.pos = data.len();
+        }
+
+        while self.pos < data.len() {
+            let pos = self.pos;
+            let remaining = data.len() - pos;
+            if !finish && remaining <= MIN_MATCH_LEN {
+                break;
+            }
+
+            let (length, offset) = self.finder.find(data, pos, max_chain_length);
+            if !finish && length == remaining {
+                self.resume_len = pos + 2 * remaining;
+                break;
+            }
+
+            if length < min_match {
+                self.pos += 1;
+                continue;
+            }
+
+            if self.options.lazy_matching && pos + 1 < data.len() {
+                let (next_length, _) = self.finder.find(data, pos + 1, max_chain_length);
+                if !finish && next_length == remaining - 1 {
+                    self.resume_len = pos + 2 * remaining;
+                    break;
+                }
+                if next_length > length {
+                    self.pos += 1;
+                    continue;
+                }
+            }
+
+            write_literals(writer, &data[self.literal_start..pos])?;
+            write_match(writer, length, offset)?;
+
+            self.pos += length;
+            self.literal_start = self.pos;
+        }
+
+        let literal_end = if finish {
+            self.pos
+        } else {
+            self.literal_start + (self.pos - self.literal_start) / MAX_LITERAL_LEN * MAX_LITERAL_LEN
+        };
+        write_literals(writer, &data[self.literal_start..literal_end])?;
+        self.literal_start = literal_end;
+
+        if finish && self.options.end_of_member {
+            writer.write_all(&END_OF_MEMBER)?;
+        }
+
+        Ok(())
+    }
+
+    /// Account for the first `shift` bytes of the input having been discarded by the caller.
+    ///
+    /// `shift` must be a multiple of `MAX_OFFSET` and no more than `oldest_needed()`.
+    pub(crate) fn rebase(&mut self, shift: usize) {
+        debug_assert_eq!(shift % MAX_OFFSET, 0);
+        debug_assert!(shift <= self.oldest_needed());
+        self.finder.rebase(shift);
+        self.literal_start -= shift;
+        self.pos -= shift;
+        self.resume_len = self.resume_len.saturating_sub(shift);
+    }
+}
+
+/// Maximum number of bytes `compress()` can write for `input_len` bytes of input.
+///
+/// The worst case is data with no matches, which is written as literal tokens of up to
+/// `MAX_LITERAL_LEN` bytes, each costing one extra control byte:
+///
+/// ```text
+/// input_len + ceil(input_len / MAX_LITERAL_LEN)
+/// ```
+///
+/// A dictionary token is never larger than the bytes it replaces, so mixing in matches cannot
+/// exceed this. Saturates at `usize::MAX` rather than overflowing.
+pub const fn compress_bound(input_len: usize) -> usize {
+    input_len.saturating_add(input_len.div_ceil(MAX_LITERAL_LEN))
+}
+
+/// Number of bits in a 3-byte prefix hash.
+const HASH_BITS: u32 = 15;
+
+/// Hash-chain match finder, as used by DEFLATE.
+///
+/// `head` maps the hash of a 3-byte prefix to the most recent position it was seen at, and `prev`
+/// links each position to the previous one with the same hash, so candidates are visited nearest
+/// first. Positions are stored plus one, leaving `0` to mark the end of a chain.
+struct MatchFinder {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    /// Every position before this one has been inserted into the chains.
+    inserted: usize,
+}
+
+impl MatchFinder {
+    fn new() -> Self {
+        MatchFinder {
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; MAX_OFFSET],
+            inserted: 0,
+        }
+    }
+
+    /// Shift every stored position down by `shift`, dropping those that fall below zero.
+    ///
+    /// Dropped positions are further back than the window can reach, so ending a chain at them
+    /// finds the same matches. `prev` stays indexed correctly as `shift` is a multiple of its length.
+    /// In store only mode nothing is inserted, so `inserted` may lag behind `shift`.
+    fn rebase(&mut self, shift: usize) {
+        for entry in self.head.iter_mut().chain(self.prev.iter_mut()) {
+            *entry = entry.saturating_sub(shift);
+        }
+        self.inserted = self.inserted.saturating_sub(shift);
+    }
+
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let prefix = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], 0]);
+        (prefix.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    /// Insert every position before `end` into the hash chains.
+    fn insert_until(&mut self, data: &[u8], end: usize) {
+        while self.inserted < end {
+            let pos = self.inserted;
+            if pos + MIN_MATCH_LEN <= data.len() {
+                let hash = Self::hash(data, pos);
+                self.prev[pos % MAX_OFFSET] = self.head[hash];
+                self.head[hash] = pos + 1;
+            }
+            self.inserted += 1;
+        }
+    }
+
+    /// Find the longest previous occurrence of the data at `pos`.
+    ///
+    /// Follows the hash chain for `pos` through up to `max_chain_length` candidates within the
+    /// sliding window, and returns `(length, offset)` of the longest match. The match may run past
+    /// `pos`, which the decoder expands as an overlapping copy.
+    ///
+    /// The search stops early at the first match of at least `MAX_MATCH_LEN` bytes, since a longer
+    /// one would only save a few control bytes.
+    fn find(&mut self, data: &[u8], pos: usize, max_chain_length: usize) -> (usize, usize) {
+        self.insert_until(data, pos);
+
+        let remaining = data.len() - pos;
+        let mut best = (0, 0);
+
+        if remaining < MIN_MATCH_LEN {
+            return best;
+        }
+
+        let mut next = self.head[Self::hash(data, pos)];
+        if next == pos + 1 {
+            // `pos` itself was inserted by an earlier lookahead search at `pos + 1`.
+            next = self.prev[pos % MAX_OFFSET];
+        }
+        for _ in 0..max_chain_length {
+            if next == 0 || pos - (next - 1) > MAX_OFFSET {
+                break;
+            }
+            let start = next - 1;
+            next = self.prev[start % MAX_OFFSET];
+            if next > start {
+                // The slot was reused by a newer position, so the chain has left the window.
+                next = 0;
+            }
+
+            // Only a candidate that also matches the byte after the current best can beat it.
+            if data[start + best.0] != data[pos + best.0] {
+                continue;
+            }
+
+            let length = data[start..]
+                .iter()
+                .zip(&data[pos..])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if length > best.0 {
+                best = (length, pos - start);
+
+                if length >= MAX_MATCH_LEN || length == remaining {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Write a run of bytes as one or more literal tokens.
+fn write_literals(writer: &mut dyn Write, bytes: &[u8]) -> Result<(), Error> {
+    for chunk in bytes.chunks(MAX_LITERAL_LEN) {
+        writer.write_all(&[(chunk.len() - 1) as u8])?;
+        writer.write_all(chunk)?;
+    }
+
+    Ok(())
+}
+
+/// Write a match as one or more dictionary tokens, using as few bytes as possible.
+///
+/// A token costs two bytes for lengths up to 8 and three bytes up to `MAX_MATCH_LEN`, so a match is
+/// never split unless it has to be. Longer matches are split into full length tokens followed by the
+/// remainder, which leaves the one short token as cheap as it can be. A remainder shorter than
+/// `MIN_MATCH_LEN` is made up by shortening the token before it.
+///
+/// A full length token at `MAX_OFFSET` is avoided, as it is reserved as `END_OF_MEMBER`.
+fn write_match(writer: &mut dyn Write, mut length: usize, offset: usize) -> Result<(), Error> {
+    let max_chunk = if offset == MAX_OFFSET {
+        MAX_MATCH_LEN - 1
+    } else {
+        MAX_MATCH_LEN
+    };
+
+    while length > 0 {
+        let mut chunk = length.min(max_chunk);
+        if length - chunk != 0 && length - chunk < MIN_MATCH_LEN {
+            chunk = length - MIN_MATCH_LEN;
+        }
+
+        write_dictionary(writer, chunk, offset)?;
+        length -= chunk;
+    }
+
+    Ok(())
+}
+
+/// Write the control bytes of a single dictionary token.
+fn write_dictionary(writer: &mut dyn Write, length: usize, offset: usize) -> Result<(), Error> {
+    let distance = offset - 1;
+    let q = (distance >> 8) as u8;
+    let s = (distance & 0xFF) as u8;
+
+    if length < 9 {
+        writer.write_all(&[((length as u8 - 2) << 5) | q, s])
+    } else {
+        writer.write_all(&[0b1110_0000 | q, (length - 9) as u8, s])
+    }
+}
+
+/// Builds compressed streams for tests one token at a time, through the encoder's own emission.
+///
+/// Each call writes exactly one token, and panics if its length or offset cannot be encoded as one,
+/// so a test states the tokens it means rather than their control bytes.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct TokenWriter(Vec<u8>);
+
+#[cfg(test)]
+impl TokenWriter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a single literal token holding `bytes`.
+    pub(crate) fn literal(mut self, bytes: &[u8]) -> Self {
+        assert!((1..=MAX_LITERAL_LEN).contains(&bytes.len()));
+        write_literals(&mut self.0, bytes).unwrap();
+        self
+    }
+
+    /// Append a single dictionary token copying `length` bytes from `offset` back.
+    pub(crate) fn dict(mut self, length: usize, offset: usize) -> Self {
+        assert!((MIN_MATCH_LEN..=MAX_MATCH_LEN).contains(&length));
+        assert!((1..=MAX_OFFSET).contains(&offset));
+        write_dictionary(&mut self.0, length, offset).unwrap();
+        self
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::decompress;
+    use crate::test_util::{noise, xorshift};
+
+    fn round_trip(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut compressed = Vec::new();
+        compress(data, &mut compressed)?;
+        Ok(decompress(Cursor::new(compressed))?)
+    }
+
+    #[test]
+    fn test_write_dictionary() -> Result<(), Error> {
+        let mut out = Vec::new();
+        write_dictionary(&mut out, 3, 15)?;
+        write_dictionary(&mut out, 5, 1)?;
+        write_dictionary(&mut out, 20, 300)?;
+        assert_eq!(out, vec![0x20, 0x0E, 0x60, 0x00, 0xE1, 0x0B, 0x2B]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_literals() -> Result<(), Error> {
+        let mut out = Vec::new();
+        write_literals(&mut out, &[0xAA; 40])?;
+        assert_eq!(out.len(), 40 + 2);
+        assert_eq!(out[0], 31);
+        assert_eq!(out[33], 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_splits_literals() -> Result<(), Box<dyn std::error::Error>> {
+        // 100 distinct bytes have no matches, so they must go out as several literal tokens.
+        let data: Vec<u8> = (0..100u8).map(|i| i.wrapping_mul(37)).collect();
+        let mut compressed = Vec::new();
+        compress(&data, &mut compressed)?;
+
+        let lengths = crate::tokens(Cursor::new(&compressed))
+            .map(|token| match token? {
+                crate::Offset::Literal { length } => Ok(length),
+                token => panic!("unexpected {:?}", token),
+            })
+            .collect::<Result<Vec<_>, crate::DecompressError>>()?;
+        assert_eq!(
+            lengths,
+            vec![MAX_LITERAL_LEN, MAX_LITERAL_LEN, MAX_LITERAL_LEN, 4]
+        );
+        assert_eq!(decompress(Cursor::new(&compressed))?, data);
+
+        // A literal control byte with q = 31 carries 32 bytes, and the byte after them starts the
+        // next token rather than being part of the literal.
+        let mut stream = vec![0x1F];
+        stream.extend_from_slice(&data[..MAX_LITERAL_LEN]);
+        stream.extend_from_slice(&[0x00, 0xEE]);
+        let mut expected = data[..MAX_LITERAL_LEN].to_vec();
+        expected.push(0xEE);
+        assert_eq!(decompress(Cursor::new(&stream))?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_dictionary_byte_order() -> Result<(), Error> {
+        // The high bits of `offset - 1` go in the control byte, the low byte last.
+        let mut out = Vec::new();
+        write_dictionary(&mut out, 3, 0x0102 + 1)?;
+        write_dictionary(&mut out, 9 + 5, 0x0102 + 1)?;
+        assert_eq!(out, [0x21, 0x02, 0xE1, 0x05, 0x02]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_writer() {
+        let stream = TokenWriter::new()
+            .literal(b"abc")
+            .dict(3, 3)
+            .dict(9, 274)
+            .dict(MAX_MATCH_LEN, 1)
+            .finish();
+        assert_eq!(
+            stream,
+            [0x02, b'a', b'b', b'c', 0x20, 0x02, 0xE1, 0x00, 0x11, 0xE0, 0xFF, 0x00]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_token_writer_rejects_invalid() {
+        TokenWriter::new().literal(b"a").dict(MAX_MATCH_LEN + 1, 1);
+    }
+
+    #[test]
+    fn test_write_match_split() -> Result<(), Error> {
+        let mut out = Vec::new();
+        write_match(&mut out, MAX_MATCH_LEN + 1, 1)?;
+        assert_eq!(out, vec![0xE0, 0xFD, 0x00, 0x20, 0x00]);
+
+        out.clear();
+        write_match(&mut out, MAX_MATCH_LEN, MAX_OFFSET)?;
+        assert_ne!(out[..3], END_OF_MEMBER);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_match_tiers() -> Result<(), Box<dyn std::error::Error>> {
+        for (length, expected) in [
+            (MIN_MATCH_LEN, 2),
+            (8, 2),
+            (9, 3),
+            (MAX_MATCH_LEN, 3),
+            (MAX_MATCH_LEN + 1, 5),
+            (MAX_MATCH_LEN + 2, 5),
+            (MAX_MATCH_LEN + 8, 5),
+            (MAX_MATCH_LEN + 9, 6),
+            (2 * MAX_MATCH_LEN + 1, 8),
+        ] {
+            let mut stream = vec![0x00, 0xAA];
+            write_match(&mut stream, length, 1)?;
+            assert_eq!(stream.len() - 2, expected, "length {}", length);
+            assert_eq!(decompress(Cursor::new(stream))?, vec![0xAA; length + 1]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_match_minimal() -> Result<(), Error> {
+        // Fewest bytes needed to encode each length, from every single token cost.
+        let mut best = vec![usize::MAX; 3 * MAX_MATCH_LEN];
+        best[0] = 0;
+        for length in MIN_MATCH_LEN..best.len() {
+            for token in MIN_MATCH_LEN..=length.min(MAX_MATCH_LEN) {
+                let cost = if token < 9 { 2 } else { 3 };
+                if best[length - token] != usize::MAX {
+                    best[length] = best[length].min(best[length - token] + cost);
+                }
+            }
+        }
+
+        for (length, &cost) in best.iter().enumerate().skip(MIN_MATCH_LEN) {
+            let mut out = Vec::new();
+            write_match(&mut out, length, 1)?;
+            assert_eq!(out.len(), cost, "length {}", length);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(round_trip(&[])?, Vec::<u8>::new());
+        assert_eq!(round_trip(b"a")?, b"a".to_vec());
+        assert_eq!(round_trip(&[7; 1000])?, vec![7; 1000]);
+        assert_eq!(
+            round_trip(b"abcabcabcabcxyzabcabc")?,
+            b"abcabcabcabcxyzabcabc".to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_bound() -> Result<(), Error> {
+        assert_eq!(compress_bound(0), 0);
+        assert_eq!(compress_bound(1), 2);
+        assert_eq!(compress_bound(MAX_LITERAL_LEN), MAX_LITERAL_LEN + 1);
+        assert_eq!(compress_bound(MAX_LITERAL_LEN + 1), MAX_LITERAL_LEN + 3);
+        assert_eq!(compress_bound(usize::MAX), usize::MAX);
+
+        // Incompressible noise from a xorshift generator is the all-literal worst case.
+        let noise = noise(0x2545_F491, 10_000);
+
+        for len in [1, 31, 32, 33, 1000, noise.len()] {
+            for level in [1, 9] {
+                let mut compressed = Vec::with_capacity(compress_bound(len));
+                compress_with_options(
+                    &noise[..len],
+                    &mut compressed,
+                    CompressOptions::level(level),
+                )?;
+                assert!(compressed.len() <= compress_bound(len), "length {}", len);
+            }
+        }
+
+        // Storing takes exactly the bound, so it cannot be any tighter.
+        for len in [0, 1, 31, 32, 33, 1000, noise.len()] {
+            let mut compressed = Vec::new();
+            compress_with_options(&noise[..len], &mut compressed, CompressOptions::level(0))?;
+            assert_eq!(compressed.len(), compress_bound(len), "length {}", len);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_levels() -> Result<(), Box<dyn std::error::Error>> {
+        let data = std::fs::read("tests/data/000.decompressed")?;
+        let mut sizes = Vec::new();
+
+        for level in 1..=9 {
+            let mut compressed = Vec::new();
+            compress_with_options(&data, &mut compressed, CompressOptions::level(level))?;
+            assert_eq!(decompress(Cursor::new(&compressed))?, data);
+            sizes.push(compressed.len());
+        }
+
+        assert!(sizes[8] <= sizes[0]);
+        assert!(CompressOptions::level(9).lazy_matching);
+        assert!(!CompressOptions::level(1).lazy_matching);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_long_match_boundary() -> Result<(), Box<dyn std::error::Error>> {
+        for (run, expected) in [
+            (9, &[9][..]),
+            (263, &[263]),
+            (264, &[264]),
+            (265, &[262, 3]),
+        ] {
+            let data = vec![b'a'; 1 + run];
+            let mut compressed = Vec::new();
+            compress(&data, &mut compressed)?;
+            assert_eq!(decompress(Cursor::new(&compressed))?, data);
+
+            let lengths: Vec<usize> = crate::tokens(Cursor::new(&compressed))
+                .skip(1)
+                .map(|token| token.map(|token| token.length()))
+                .collect::<Result<_, _>>()?;
+            assert_eq!(lengths, expected, "run of {}", run);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_store_only() -> Result<(), Box<dyn std::error::Error>> {
+        let text = std::fs::read("tests/data/000.decompressed")?;
+        let noise = noise(0x9E37_79B9, 10_000);
+
+        for data in [&text[..], &noise, &[0; 1000], &[]] {
+            let mut compressed = Vec::new();
+            compress_with_options(data, &mut compressed, CompressOptions::level(0))?;
+            assert_eq!(compressed.len(), compress_bound(data.len()));
+            assert_eq!(decompress(Cursor::new(&compressed))?, data);
+
+            for token in crate::tokens(Cursor::new(&compressed)) {
+                assert!(matches!(token?, crate::Offset::Literal { .. }));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_never_beaten_by_store() -> Result<(), Box<dyn std::error::Error>> {
+        let mut next = xorshift(0x1234_5678);
+
+        for len in [1, 31, 32, 33, 100, 1000, 10_000] {
+            // Random bytes hold the odd short match, but never come out larger than stored.
+            let noise: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            let mut compressed = Vec::new();
+            compress(&noise, &mut compressed)?;
+            let mut stored = Vec::new();
+            compress_with_options(&noise, &mut stored, CompressOptions::level(0))?;
+            assert!(compressed.len() <= stored.len());
+            assert_eq!(stored.len(), compress_bound(len));
+
+            // Short matches scattered through random input never cost more than storing it.
+            for alphabet in [2, 3, 8] {
+                let data: Vec<u8> = (0..len).map(|_| (next() % alphabet) as u8).collect();
+                for level in [1, 6, 9] {
+                    let mut compressed = Vec::new();
+                    compress_with_options(&data, &mut compressed, CompressOptions::level(level))?;
+                    assert!(compressed.len() <= compress_bound(data.len()));
+                    assert_eq!(decompress(Cursor::new(&compressed))?, data);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_min_match() -> Result<(), Box<dyn std::error::Error>> {
+        let data = std::fs::read("tests/data/000.decompressed")?;
+        let mut sizes = Vec::new();
+
+        for min_match in [0, MIN_MATCH_LEN, 6, 16] {
+            let options = CompressOptions {
+                min_match,
+                ..CompressOptions::default()
+            };
+            let mut compressed = Vec::new();
+            compress_with_options(&data, &mut compressed, options)?;
+            assert_eq!(decompress(Cursor::new(&compressed))?, data);
+
+            for token in crate::tokens(Cursor::new(&compressed)) {
+                if let crate::Offset::Dictionary { length, .. } = token? {
+                    assert!(length >= min_match);
+                }
+            }
+            sizes.push(compressed.len());
+        }
+
+        assert_eq!(sizes[0], sizes[1]);
+        assert!(sizes[1] < sizes[2]);
+        assert!(sizes[2] < sizes[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_finder() {
+        let noise = noise(0x1234_5678, 1000);
+        let data = [&noise[..], &[0; 4000], &noise[..]].concat();
+
+        let mut finder = MatchFinder::new();
+        assert_eq!(finder.find(&data, 5000, 16), (1000, 5000));
+
+        let mut finder = MatchFinder::new();
+        assert_eq!(finder.find(&data, 1001, 16), (3999, 1));
+    }
+
+    #[test]
+    fn test_compress_large() -> Result<(), Box<dyn std::error::Error>> {
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        let mut compressed = Vec::new();
+        compress(&data, &mut compressed)?;
+        assert!(compressed.len() < data.len() / 50);
+        assert_eq!(decompress(Cursor::new(compressed))?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_file() -> Result<(), Box<dyn std::error::Error>> {
+        let data = std::fs::read("tests/data/000.decompressed")?;
+        assert_eq!(round_trip(&data)?, data);
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::{self, Write};
+
+use crate::compress::Encoder;
+use crate::{CompressOptions, MAX_OFFSET};
+
+/// Input discarded at once when trimming, so the match finder is only rebased occasionally.
+const TRIM_CHUNK: usize = 4 * MAX_OFFSET;
+
+/// A streaming compressor that encodes data as it is written.
+///
+/// Wraps a writer of compressed data and implements `Write` over the uncompressed input. Tokens
+/// are written out as soon as they are known, but a match running into the end of the input so far
+/// might grow once more arrives, so the tail is held back until the next write or `finish()`. The
+/// output is identical to `compress_with_options()` on the whole input, however it is split.
+///
+/// Only the sliding window and the pending tail are kept in memory, apart from a run that keeps
+/// matching, which is held until it ends.
+///
+/// `flush()` only flushes the underlying writer; call `finish()` to write out the held back tail.
+/// Dropping a `Compressor` without finishing it loses the tail.
+pub struct Compressor<W: Write> {
+    writer: W,
+    encoder: Encoder,
+    /// Input still needed for matching or not yet encoded.
+    data: Vec<u8>,
+}
+
+impl<W: Write> Compressor<W> {
+    /// Create a compressor writing compressed data to `writer`, with the default options.
+    pub fn new(writer: W) -> Self {
+        Compressor::with_options(writer, CompressOptions::default())
+    }
+
+    /// Create a compressor writing compressed data to `writer`, with the given tuning options.
+    pub fn with_options(writer: W, options: CompressOptions) -> Self {
+        Compressor {
+            writer,
+            encoder: Encoder::new(options),
+            data: Vec::new(),
+        }
+    }
+
+    /// Encode the held back tail of the input and return the underlying writer.
+    ///
+    /// Nothing else is written unless `CompressOptions::end_of_member` is set, in which case the
+    /// stream is terminated with `END_OF_MEMBER`. The writer is flushed.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.encoder.encode(&self.data, &mut self.writer, true)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Discard input that can no longer be referenced or written as a literal.
+    fn trim(&mut self) {
+        let shift = self.encoder.oldest_needed() / TRIM_CHUNK * TRIM_CHUNK;
+        if shift > 0 {
+            self.data.drain(..shift);
+            self.encoder.rebase(shift);
+        }
+    }
+}
+
+impl<W: Write> Write for Compressor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        self.encoder.encode(&self.data, &mut self.writer, false)?;
+        self.trim();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress_with_options;
+    use crate::test_util::xorshift;
+
+    fn inputs() -> Vec<Vec<u8>> {
+        let mut next = xorshift(0x2545_F491);
+
+        let noise: Vec<u8> = (0..50_000).map(|_| next() as u8).collect();
+        let text: Vec<u8> = (0..50_000).map(|_| b"abcd "[next() as usize % 5]).collect();
+        let mut runs = Vec::new();
+        while runs.len() < 100_000 {
+            runs.extend(std::iter::repeat_n(
+                next() as u8,
+                next() as usize % 2000 + 1,
+            ));
+        }
+
+        vec![
+            Vec::new(),
+            b"abcabcabc".to_vec(),
+            std::fs::read("tests/data/000.decompressed").unwrap(),
+            noise,
+            text,
+            runs,
+            vec![0; 3 * TRIM_CHUNK],
+        ]
+    }
+
+    #[test]
+    fn test_compressor_chunked() -> io::Result<()> {
+        for data in inputs() {
+            for level in [0, 1, 9] {
+                let options = CompressOptions::level(level);
+                let mut expected = Vec::new();
+                compress_with_options(&data, &mut expected, options)?;
+
+                for chunk_size in [1, 7, 1000, data.len().max(1)] {
+                    let mut compressor = Compressor::with_options(Vec::new(), options);
+                    for chunk in data.chunks(chunk_size) {
+                        compressor.write_all(chunk)?;
+                    }
+                    let output = compressor.finish()?;
+                    assert!(
+                        output == expected,
+                        "{} bytes at level {} in chunks of {}",
+                        data.len(),
+                        level,
+                        chunk_size
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressor_bounded() -> io::Result<()> {
+        let mut next = xorshift(0x9E37_79B9);
+        let mut compressor = Compressor::new(Vec::new());
+        for _ in 0..1000 {
+            let chunk: Vec<u8> = (0..1000).map(|_| next() as u8).collect();
+            compressor.write_all(&chunk)?;
+            assert!(compressor.data.len() <= MAX_OFFSET + TRIM_CHUNK + chunk.len());
+        }
+
+        let output = compressor.finish()?;
+        assert_eq!(crate::decompress_slice(&output).unwrap().len(), 1_000_000);
+        Ok(())
+    }
+}
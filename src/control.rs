@@ -0,0 +1,309 @@
+/// The first byte of a token, which selects its length tier and holds the top bits of its data.
+///
+/// The top three bits pick the tier: `000` is a literal, `001` to `110` are dictionary copies of
+/// 3 to 8 bytes, and `111` is a copy of 9 or more bytes whose length is extended by a follow-up
+/// byte. The low five bits are the `q` bits, holding the literal length minus one, or the high bits
+/// of the dictionary offset minus one, whose low byte is the token's last follow-up byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlByte(u8);
+
+impl ControlByte {
+    /// Wrap a raw control byte.
+    pub const fn new(byte: u8) -> Self {
+        ControlByte(byte)
+    }
+
+    /// The raw control byte.
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// The length tier selected by the top three bits.
+    pub const fn tier(self) -> LengthTier {
+        TIERS[self.0 as usize]
+    }
+
+    /// The low five bits, whose meaning depends on the tier.
+    pub const fn q_bits(self) -> u8 {
+        self.0 & 0b0001_1111
+    }
+}
+
+impl From<u8> for ControlByte {
+    fn from(byte: u8) -> Self {
+        ControlByte(byte)
+    }
+}
+
+/// The kind of token a control byte introduces.
+///
+/// Each variant's discriminant is the variant code used internally by the parser: `1` for a
+/// literal, and the match length for the dictionary tiers, with `9` standing for 9 or more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum LengthTier {
+    /// A literal of `1 + q` bytes, copied from the stream.
+    Literal = 1,
+    /// A 3 byte dictionary copy followed by one offset byte.
+    Dict3 = 3,
+    /// A 4 byte dictionary copy followed by one offset byte.
+    Dict4 = 4,
+    /// A 5 byte dictionary copy followed by one offset byte.
+    Dict5 = 5,
+    /// A 6 byte dictionary copy followed by one offset byte.
+    Dict6 = 6,
+    /// A 7 byte dictionary copy followed by one offset byte.
+    Dict7 = 7,
+    /// An 8 byte dictionary copy followed by one offset byte.
+    Dict8 = 8,
+    /// A dictionary copy of `9 + r` bytes, followed by the length byte `r` and one offset byte.
+    DictLong = 9,
+}
+
+impl LengthTier {
+    /// Every tier, in order of their top bits.
+    pub const ALL: [LengthTier; 8] = [
+        LengthTier::Literal,
+        LengthTier::Dict3,
+        LengthTier::Dict4,
+        LengthTier::Dict5,
+        LengthTier::Dict6,
+        LengthTier::Dict7,
+        LengthTier::Dict8,
+        LengthTier::DictLong,
+    ];
+
+    /// The tier selected by a raw control byte, as `ControlByte::tier()`.
+    pub const fn from_control_byte(byte: u8) -> LengthTier {
+        ControlByte::new(byte).tier()
+    }
+
+    /// The top three bits of every control byte in this tier, in place and with the `q` bits
+    /// clear, so an encoder can build a control byte as `tier.to_top_bits() | q`.
+    pub const fn to_top_bits(&self) -> u8 {
+        let pattern = match self {
+            LengthTier::Literal => 0b000,
+            LengthTier::Dict3 => 0b001,
+            LengthTier::Dict4 => 0b010,
+            LengthTier::Dict5 => 0b011,
+            LengthTier::Dict6 => 0b100,
+            LengthTier::Dict7 => 0b101,
+            LengthTier::Dict8 => 0b110,
+            LengthTier::DictLong => 0b111,
+        };
+        pattern << 5
+    }
+}
+
+/// Description of one length tier of the control byte format, see `CONTROL_FORMAT`.
+///
+/// In the formulas, `q` is the low five bits of the control byte, and `r` and `s` are its first
+/// and second follow-up bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlSpec {
+    /// The tier described.
+    pub tier: LengthTier,
+    /// The top three bits of every control byte in this tier.
+    pub pattern: u8,
+    /// Number of follow-up bytes after the control byte, not counting literal data.
+    pub follow_bytes: usize,
+    /// Output length of the token.
+    pub length: &'static str,
+    /// Dictionary offset of the token, or `"-"` for a literal.
+    pub offset: &'static str,
+    /// Shortest output a token in this tier encodes.
+    pub min_length: usize,
+    /// Longest output a token in this tier encodes.
+    pub max_length: usize,
+}
+
+/// Spec of a dictionary copy tier with a fixed `length` and one offset byte.
+const fn copy_spec(tier: LengthTier, pattern: u8, length: usize) -> ControlSpec {
+    ControlSpec {
+        tier,
+        pattern,
+        follow_bytes: 1,
+        length: match length {
+            3 => "3",
+            4 => "4",
+            5 => "5",
+            6 => "6",
+            7 => "7",
+            8 => "8",
+            _ => panic!("not a single byte copy length"),
+        },
+        offset: "(q << 8) + r + 1",
+        min_length: length,
+        max_length: length,
+    }
+}
+
+/// Every length tier of the control byte format, in order of the top three bits.
+///
+/// The same format the decoder implements, laid out for encoders and validators to iterate rather
+/// than reverse-engineer from the parser.
+pub const CONTROL_FORMAT: &[ControlSpec] = &[
+    ControlSpec {
+        tier: LengthTier::Literal,
+        pattern: 0b000,
+        follow_bytes: 0,
+        length: "1 + q",
+        offset: "-",
+        min_length: 1,
+        max_length: crate::MAX_LITERAL_LEN,
+    },
+    copy_spec(LengthTier::Dict3, 0b001, 3),
+    copy_spec(LengthTier::Dict4, 0b010, 4),
+    copy_spec(LengthTier::Dict5, 0b011, 5),
+    copy_spec(LengthTier::Dict6, 0b100, 6),
+    copy_spec(LengthTier::Dict7, 0b101, 7),
+    copy_spec(LengthTier::Dict8, 0b110, 8),
+    ControlSpec {
+        tier: LengthTier::DictLong,
+        pattern: 0b111,
+        follow_bytes: 2,
+        length: "9 + r",
+        offset: "(q << 8) + s + 1",
+        min_length: 9,
+        max_length: crate::MAX_MATCH_LEN,
+    },
+];
+
+/// Length tier of every possible control byte, indexed by the byte itself.
+const TIERS: [LengthTier; 256] = build_tiers();
+
+/// Generate `TIERS` from `cb_mask_ladder` at compile time.
+const fn build_tiers() -> [LengthTier; 256] {
+    let mut table = [LengthTier::Literal; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = match cb_mask_ladder(i as u8) {
+            Some(1) => LengthTier::Literal,
+            Some(3) => LengthTier::Dict3,
+            Some(4) => LengthTier::Dict4,
+            Some(5) => LengthTier::Dict5,
+            Some(6) => LengthTier::Dict6,
+            Some(7) => LengthTier::Dict7,
+            Some(8) => LengthTier::Dict8,
+            Some(9) => LengthTier::DictLong,
+            _ => panic!("control byte without a length tier"),
+        };
+        i += 1;
+    }
+    table
+}
+
+/// Reference bitmask comparisons that `TIERS` is generated from.
+///
+/// Returns `None` if the byte does not match any known variant.
+const fn cb_mask_ladder(i: u8) -> Option<u8> {
+    if i | 0b0001_1111 == 0b0001_1111 {
+        return Some(1);
+    }
+
+    if i | 0b0011_1111 == 0b0011_1111 {
+        return Some(3);
+    }
+
+    if i | 0b0101_1111 == 0b0101_1111 {
+        return Some(4);
+    }
+
+    if i | 0b0111_1111 == 0b0111_1111 {
+        return Some(5);
+    }
+
+    if i | 0b1001_1111 == 0b1001_1111 {
+        return Some(6);
+    }
+
+    if i | 0b1011_1111 == 0b1011_1111 {
+        return Some(7);
+    }
+
+    if i | 0b1101_1111 == 0b1101_1111 {
+        return Some(8);
+    }
+
+    if i | 0b1111_1111 == 0b1111_1111 {
+        return Some(9);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tier() {
+        assert_eq!(ControlByte::new(0b0000_0001).tier(), LengthTier::Literal);
+        assert_eq!(ControlByte::new(0b0010_0001).tier(), LengthTier::Dict3);
+        assert_eq!(ControlByte::new(0b1100_0101).tier(), LengthTier::Dict8);
+        assert_eq!(ControlByte::new(0b1110_0001).tier(), LengthTier::DictLong);
+    }
+
+    #[test]
+    fn test_tier_top_bits() {
+        for tier in LengthTier::ALL {
+            for q in [0, 1, 0b1_1111] {
+                let byte = tier.to_top_bits() | q;
+                assert_eq!(LengthTier::from_control_byte(byte), tier);
+                assert_eq!(ControlByte::new(byte).q_bits(), q);
+            }
+        }
+        for i in 0..=u8::MAX {
+            assert_eq!(
+                LengthTier::from_control_byte(i).to_top_bits(),
+                i & 0b1110_0000
+            );
+        }
+    }
+
+    #[test]
+    fn test_tier_table() {
+        for i in 0..=u8::MAX {
+            assert_eq!(Some(ControlByte::new(i).tier() as u8), cb_mask_ladder(i));
+        }
+    }
+
+    #[test]
+    fn test_control_format() {
+        use crate::decompress::{follow_len, parse_token};
+        use crate::Offset;
+
+        for i in 0..=u8::MAX {
+            let spec = CONTROL_FORMAT[(i >> 5) as usize];
+            assert_eq!(spec.pattern, i >> 5);
+            assert_eq!(spec.tier, ControlByte::new(i).tier());
+            assert_eq!(spec.tier.to_top_bits(), spec.pattern << 5);
+            assert_eq!(spec.follow_bytes, follow_len(i));
+        }
+
+        for spec in CONTROL_FORMAT {
+            let cb = spec.pattern << 5;
+            let follow = &[0x00, 0x00][..spec.follow_bytes];
+            let longest = &[0xFF, 0xFF][..spec.follow_bytes];
+            for (cb, follow, length) in [
+                (cb, follow, spec.min_length),
+                (cb | 0b1_1111, longest, spec.max_length),
+            ] {
+                assert_eq!(parse_token(cb, follow).unwrap().length(), length);
+            }
+            assert_eq!(
+                matches!(parse_token(cb, follow).unwrap(), Offset::Literal { .. }),
+                spec.tier == LengthTier::Literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_q_bits() {
+        assert_eq!(ControlByte::from(0b1110_0001).q_bits(), 1);
+        assert_eq!(ControlByte::from(0b0000_0011).q_bits(), 3);
+        assert_eq!(ControlByte::from(0xFF).get(), 0xFF);
+    }
+}
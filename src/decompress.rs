@@ -1,165 +1,995 @@
-use std::io::Read;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Read};
 
-/// Error type returned by decompress() and helper methods.
-type Error = Box<dyn std::error::Error>;
+use crate::canonical::CanonicalCheck;
+#[cfg(feature = "std")]
+use crate::source::BufSource;
+use crate::source::{ByteSource, CountingSource};
+use crate::trace::Trace;
+use crate::DecompressError;
+use crate::{ControlByte, Decompress, DecompressStats, LengthTier, TraceEntry};
 
 /// Decompress a data stream from the reader.
 ///
-/// Reads compressed data from `reader` and return the result as an array of bytes.
+/// Reads compressed data from `reader` and return the result as an array of bytes. The reader is
+/// buffered internally and read through to the end, so there is no need to wrap it in a
+/// `BufReader`; use `decompress_buffered()` for a reader that is already buffered.
 ///
 /// Returns a `Result` with a decompression error if there are any issues reading or writing data.
-pub fn decompress<R: Read>(mut reader: R) -> Result<Vec<u8>, Error> {
+#[cfg(feature = "std")]
+pub fn decompress<R: Read>(reader: R) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().read_from(reader)
+}
+
+/// Decompress a data stream from the reader into a pre-sized buffer.
+///
+/// Behaves like `decompress()`, but reserves `expected_size` bytes for the output up front.
+/// Useful when the decompressed size is known ahead of time (e.g. from a file header), as it
+/// avoids repeatedly reallocating the output while it grows.
+#[cfg(feature = "std")]
+pub fn decompress_with_capacity<R: Read>(
+    reader: R,
+    expected_size: usize,
+) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().capacity(expected_size).read_from(reader)
+}
+
+/// Decompress a data stream from the reader into a boxed slice with no spare capacity.
+///
+/// Behaves like `decompress()`, but releases the capacity the output over-allocated while it grew,
+/// for decoded buffers that are kept around for a long time. Shrinking is a single reallocation
+/// and copy at the end, which is cheaper than keeping the output tight as it grows; to avoid even
+/// that, reserve the exact size with `Decompress::capacity()` when it is known.
+#[cfg(feature = "std")]
+pub fn decompress_boxed<R: Read>(reader: R) -> Result<Box<[u8]>, DecompressError> {
+    decompress(reader).map(Vec::into_boxed_slice)
+}
+
+/// Decompress a data stream from a buffered reader.
+///
+/// Behaves like `decompress()`, but pulls control and data bytes straight out of the reader's own
+/// buffer with `fill_buf()` and `consume()`, rather than buffering them a second time.
+#[cfg(feature = "std")]
+pub fn decompress_buffered<R: BufRead>(reader: R) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().read_buffered(reader)
+}
+
+/// Wrap `reader` so the decoder reads it in blocks instead of a byte at a time.
+#[cfg(feature = "std")]
+pub(crate) fn buffered<R: Read>(reader: R) -> BufSource<BufReader<R>> {
+    BufSource::new(BufReader::new(reader))
+}
+
+/// Decompress a data stream from the reader, refusing to produce more than `max_output` bytes.
+///
+/// Guards against decompression bombs, where a tiny stream of long dictionary references expands
+/// into a huge output. Each token's length is checked against the limit before any of its output
+/// is allocated, and `DecompressError::OutputLimitExceeded` is returned if it would overshoot.
+#[cfg(feature = "std")]
+pub fn decompress_with_limit<R: Read>(
+    reader: R,
+    max_output: usize,
+) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().max_output(max_output).read_from(reader)
+}
+
+/// Decompress a data stream from the reader against a preset dictionary.
+///
+/// The decoder starts with `preset` already in its dictionary, so dictionary tokens at the start
+/// of the stream can reference data agreed out of band (like zlib's `inflateSetDictionary`).
+/// Only the newly decoded bytes are returned, not the preset prefix.
+#[cfg(feature = "std")]
+pub fn decompress_with_dictionary<R: Read>(
+    reader: R,
+    preset: &[u8],
+) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new()
+        .preset_dictionary(preset)
+        .read_from(reader)
+}
+
+/// Decompress only the first `max_bytes` bytes of a data stream from the reader.
+///
+/// Useful for previewing large streams: decoding stops at the first token that reaches
+/// `max_bytes` of output, and that token's output is cut short. A dictionary token is only copied
+/// as far as `max_bytes`, which for an overlapping copy gives the same bytes as the start of the
+/// whole copy. The reader is not buffered internally, so nothing past that token is read from it.
+#[cfg(feature = "std")]
+pub fn decompress_prefix<R: Read>(reader: R, max_bytes: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut dictionary = Vec::new();
+    let options = DecodeOptions {
+        stop_at: max_bytes,
+        ..DecodeOptions::default()
+    };
+    decode(reader, &mut dictionary, 0, options)?;
+
+    dictionary.truncate(max_bytes);
+    Ok(dictionary)
+}
+
+/// Most output `decompress_framed()` reserves up front, however large the header claims it is.
+#[cfg(feature = "std")]
+const FRAME_RESERVE_LIMIT: usize = 1 << 20;
+
+/// Decompress a frame holding a little-endian `u32` decompressed length followed by a data stream.
+///
+/// The stream must produce exactly the length in the header. Output past it is rejected with
+/// `DecompressError::OutputLimitExceeded` as soon as a token would overshoot, and a stream ending
+/// short of it with `DecompressError::LengthMismatch`. The header is also used to reserve the
+/// output up front, though only up to 1 MiB so a corrupt header cannot force a huge allocation.
+#[cfg(feature = "std")]
+pub fn decompress_framed<R: Read>(mut reader: R) -> Result<Vec<u8>, DecompressError> {
+    let mut header = [0u8; 4];
+    reader.read_exact_into(&mut header)?;
+    let expected = u32::from_le_bytes(header) as usize;
+
+    let output = Decompress::new()
+        .capacity(expected.min(FRAME_RESERVE_LIMIT))
+        .max_output(expected)
+        .read_from(reader)?;
+
+    if output.len() != expected {
+        return Err(DecompressError::LengthMismatch {
+            expected,
+            actual: output.len(),
+        });
+    }
+    Ok(output)
+}
+
+/// Decompress a data stream from the reader, appending the output to `out`.
+///
+/// Lets many streams be decoded into one growing buffer without a separate allocation for each.
+/// Dictionary tokens may only reference bytes appended by this call, never what `out` held before,
+/// so each stream decodes exactly as it would on its own. On error `out` is truncated back to its
+/// original length.
+///
+/// `out` only ever grows: it is never shrunk, and is reallocated only if the output overflows its
+/// spare capacity. A recycled buffer reserved for at least the decompressed length is filled in
+/// place, so the only allocation made is the buffer `reader` is read through.
+///
+/// Returns the number of bytes appended.
+#[cfg(feature = "std")]
+pub fn decompress_append<R: Read>(reader: R, out: &mut Vec<u8>) -> Result<usize, DecompressError> {
+    let base = out.len();
+    match decode(buffered(reader), out, base, DecodeOptions::default()) {
+        Ok(_) => Ok(out.len() - base),
+        Err(err) => {
+            out.truncate(base);
+            Err(err)
+        }
+    }
+}
+
+/// Decompress a data stream from the reader, reporting how much input was consumed.
+///
+/// Returns the decompressed output along with the number of compressed bytes read, including every
+/// control and follow-up byte, up to the clean end of stream.
+#[cfg(feature = "std")]
+pub fn decompress_counted<R: Read>(reader: R) -> Result<(Vec<u8>, usize), DecompressError> {
+    let mut dictionary = Vec::new();
+    let stats = decode(
+        buffered(reader),
+        &mut dictionary,
+        0,
+        DecodeOptions::default(),
+    )?;
+    Ok((dictionary, stats.compressed_bytes))
+}
+
+/// Decompress a data stream from the reader, reporting statistics about its tokens.
+///
+/// Returns the decompressed output along with counts of the literal and dictionary tokens, the
+/// bytes each produced, and the compressed length, all gathered during the same decoding pass.
+#[cfg(feature = "std")]
+pub fn decompress_with_stats<R: Read>(
+    reader: R,
+) -> Result<(Vec<u8>, DecompressStats), DecompressError> {
+    let mut dictionary = Vec::new();
+    let stats = decode(
+        buffered(reader),
+        &mut dictionary,
+        0,
+        DecodeOptions::default(),
+    )?;
+    Ok((dictionary, stats))
+}
+
+/// Decompress a data stream from the reader, feeding the output to `hasher` as it is decoded.
+///
+/// Each token's output is passed to `hasher.write()` while it is still hot in cache, avoiding a
+/// second pass over the result. Comparing `hasher.finish()` against a digest stored alongside the
+/// compressed data detects corruption that still decodes cleanly.
+///
+/// The output is written in token sized pieces, so `hasher` must produce the same digest however
+/// its input is split, as any streaming checksum does.
+#[cfg(feature = "std")]
+pub fn decompress_and_digest<R: Read, H: Hasher>(
+    reader: R,
+    hasher: &mut H,
+) -> Result<Vec<u8>, DecompressError> {
+    let mut dictionary = Vec::new();
+    decode_with(
+        buffered(reader),
+        &mut dictionary,
+        0,
+        DecodeOptions::default(),
+        |token| hasher.write(token.output),
+    )?;
+    Ok(dictionary)
+}
+
+/// Decompress a data stream from the reader, reporting progress after every token.
+///
+/// `on_progress` is called with the number of compressed bytes consumed and decompressed bytes
+/// produced so far, both of which only ever grow. It runs once per token, which can be every few
+/// bytes of output, so it should be cheap, e.g. only redrawing a progress bar when the percentage
+/// changes.
+#[cfg(feature = "std")]
+pub fn decompress_with_progress<R: Read, F: FnMut(usize, usize)>(
+    reader: R,
+    mut on_progress: F,
+) -> Result<Vec<u8>, DecompressError> {
     let mut dictionary = Vec::new();
+    decode_with(
+        buffered(reader),
+        &mut dictionary,
+        0,
+        DecodeOptions::default(),
+        |token| on_progress(token.input_len, token.output_len),
+    )?;
+    Ok(dictionary)
+}
+
+/// Decompress a stream of several independently compressed members from the reader.
+///
+/// Each member is terminated by an `END_OF_MEMBER` token, at which point the dictionary is cleared
+/// so the next member cannot reference earlier ones. A final member without a terminator is
+/// returned as well, so a plain single-member stream decodes to a single member.
+///
+/// The terminator shares its encoding with a `MAX_MATCH_LEN` copy at `MAX_OFFSET`, so members
+/// must not contain that token. `compress()` never emits it, and terminates each stream with one
+/// when `CompressOptions::end_of_member` is set.
+#[cfg(feature = "std")]
+pub fn decompress_members<R: Read>(reader: R) -> Result<Vec<Vec<u8>>, DecompressError> {
+    let mut output = Vec::new();
+    let mut lengths = Vec::new();
+    let options = DecodeOptions {
+        members: true,
+        ..DecodeOptions::default()
+    };
+    decode_with(buffered(reader), &mut output, 0, options, |token| {
+        if token.offset == Offset::EndOfMember {
+            lengths.push(token.output_len);
+        }
+    })?;
+
+    let mut rest = output.as_slice();
+    let mut members: Vec<Vec<u8>> = lengths
+        .into_iter()
+        .map(|len| {
+            let (member, tail) = rest.split_at(len);
+            rest = tail;
+            member.to_vec()
+        })
+        .collect();
+    if !rest.is_empty() {
+        members.push(rest.to_vec());
+    }
+
+    Ok(members)
+}
+
+/// Decompress a data stream that may hold one member or several, concatenating their output.
+///
+/// Like `gunzip` on a multi-member file: each `END_OF_MEMBER` terminator that is followed by more
+/// input starts a new member, whose tokens cannot reference the output of earlier ones. A
+/// terminator at the very end of the input just ends the last member. A plain single-member stream,
+/// as written by `compress()`, decodes exactly as `decompress()` decodes it.
+///
+/// As with `decompress_members()`, members must not contain a `MAX_MATCH_LEN` copy at
+/// `MAX_OFFSET`, the encoding shared with the terminator.
+#[cfg(feature = "std")]
+pub fn decompress_auto<R: Read>(reader: R) -> Result<Vec<u8>, DecompressError> {
+    let mut output = Vec::new();
+    let options = DecodeOptions {
+        members: true,
+        ..DecodeOptions::default()
+    };
+    decode(buffered(reader), &mut output, 0, options)?;
+    Ok(output)
+}
+
+/// Reinterpret the reserved end of member encoding as `Offset::EndOfMember`.
+pub(crate) fn member_token(offset: Offset) -> Offset {
+    match offset {
+        Offset::Dictionary {
+            length: crate::MAX_MATCH_LEN,
+            offset: crate::MAX_OFFSET,
+        } => Offset::EndOfMember,
+        offset => offset,
+    }
+}
+
+/// Decompress an in-memory data stream.
+///
+/// Behaves like `decompress()`, but walks `input` directly by index instead of going through the
+/// `Read` trait, avoiding the per-byte overhead of the reader for payloads already in memory.
+pub fn decompress_slice(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().from_slice(input)
+}
+
+/// Decompress an in-memory data stream from anything that can be viewed as bytes.
+///
+/// The recommended entry point for compressed data already in memory, accepting a `Vec<u8>`,
+/// `&[u8]`, array or string directly, without wrapping it in a `Cursor`. Decodes through
+/// `decompress_slice()`.
+pub fn decompress_bytes<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecompressError> {
+    decompress_slice(input.as_ref())
+}
+
+/// Decompress a data stream from an iterator of bytes.
+///
+/// Lets the output of another decoding stage be piped straight in, without collecting it into a
+/// buffer or adapting it to `Read` first. The stream ends when the iterator does.
+pub fn decompress_iter<I: IntoIterator<Item = u8>>(iter: I) -> Result<Vec<u8>, DecompressError> {
+    Decompress::new().read_iter(iter)
+}
+
+/// Checks and limits applied by `decode()`, mirroring the options of `Decompress`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DecodeOptions {
+    /// Fail without applying a token that would grow the dictionary past this many bytes.
+    pub(crate) max_output: usize,
+    /// Fail without reading a token that would end past this many bytes of input.
+    pub(crate) max_input: usize,
+    /// Treat an `END_OF_MEMBER` terminator as the end of the stream, rejecting any input after it
+    /// with `DecompressError::TrailingData`.
+    pub(crate) strict_eof: bool,
+    /// Reject tokens not split the way the encoder splits them with
+    /// `DecompressError::NonCanonical`.
+    pub(crate) canonical: bool,
+    /// Wrap an error in `DecompressError::Traced` along with the last tokens applied before it.
+    pub(crate) trace: bool,
+    /// Reject any literal token with `DecompressError::UnexpectedLiteral`.
+    pub(crate) forbid_literals: bool,
+    /// Stop before reading another token once the output reaches this many bytes, cutting short a
+    /// dictionary token that would pass it. A literal token is still applied in full.
+    pub(crate) stop_at: usize,
+    /// Treat each `END_OF_MEMBER` terminator as the start of a new member, whose tokens cannot
+    /// reference the output before it.
+    pub(crate) members: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            max_output: usize::MAX,
+            max_input: usize::MAX,
+            strict_eof: false,
+            canonical: false,
+            trace: false,
+            forbid_literals: false,
+            stop_at: usize::MAX,
+            members: false,
+        }
+    }
+}
+
+/// Decode every token from `source`, appending the output to `dictionary`.
+///
+/// Dictionary tokens may only reach back as far as index `base`, so anything already in
+/// `dictionary` before it is kept but cannot be referenced.
+///
+/// Every token applied adds at least one byte, since `apply_token_at()` rejects empty ones, so the
+/// loop always makes progress through both the input and the output. See `DecodeOptions` for the
+/// checks made along the way.
+///
+/// Returns statistics on the decoded tokens, including the number of bytes read from `source`.
+pub(crate) fn decode<S: ByteSource>(
+    source: S,
+    dictionary: &mut Vec<u8>,
+    base: usize,
+    options: DecodeOptions,
+) -> Result<DecompressStats, DecompressError> {
+    decode_with(source, dictionary, base, options, |_| {})
+}
+
+/// A token applied by `decode_with()`, as passed to its callback.
+///
+/// Only the tracer reads it without `std`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) struct Applied<'a> {
+    /// Position of the token's control byte in the compressed stream.
+    pub(crate) input_pos: usize,
+    /// Compressed bytes read so far, up to the end of the token.
+    pub(crate) input_len: usize,
+    pub(crate) offset: Offset,
+    /// Output of the current member so far, counted from `base`, including the token's own.
+    pub(crate) output_len: usize,
+    /// The token's output, or nothing if `output` does not keep it.
+    pub(crate) output: &'a [u8],
+}
+
+/// Decode as `decode()` does into any `TokenOutput`, calling `on_token` after every token.
+///
+/// With `DecodeOptions::members` set, `on_token` is also called with each `END_OF_MEMBER`
+/// terminator, just before the member it ends is closed.
+pub(crate) fn decode_with<S, O, F>(
+    source: S,
+    output: &mut O,
+    base: usize,
+    options: DecodeOptions,
+    mut on_token: F,
+) -> Result<DecompressStats, DecompressError>
+where
+    S: ByteSource,
+    O: TokenOutput,
+    F: FnMut(&Applied<'_>),
+{
+    if !options.trace {
+        return decode_tokens(source, output, base, options, on_token);
+    }
+
+    let mut recent = Trace::default();
+    decode_tokens(source, output, base, options, |token| {
+        recent.record(TraceEntry {
+            input_pos: token.input_pos,
+            offset: token.offset,
+            output_len: token.output_len,
+        });
+        on_token(token);
+    })
+    .map_err(|err| recent.attach(err))
+}
+
+/// The decoding loop behind `decode_with()`, written once for every decoding entry point.
+fn decode_tokens<S, O, F>(
+    source: S,
+    output: &mut O,
+    mut base: usize,
+    options: DecodeOptions,
+    mut on_token: F,
+) -> Result<DecompressStats, DecompressError>
+where
+    S: ByteSource,
+    O: TokenOutput,
+    F: FnMut(&Applied<'_>),
+{
+    let mut source = CountingSource::new(source);
+    let mut stats = DecompressStats::default();
+    let mut canonical_check = options.canonical.then(CanonicalCheck::default);
 
     loop {
-        match get_control_bytes(&mut reader) {
-            Ok(offset) => {
-                match offset {
-                    Offset::Dictionary { length, offset } => {
-                        let dict = fetch_offset(&dictionary, length, offset)?;
-                        dictionary.extend_from_slice(&dict);
-                    }
-                    Offset::Literal { length } => match read_bytes(&mut reader, length) {
-                        Ok(bytes) => {
-                            dictionary.append(&mut bytes.to_vec());
-                        }
-                        Err(_) => {
-                            return Err("Cannot take any more literal bytes, reached end of compressed buffer.".into());
-                        }
-                    },
-                }
+        let input_pos = source.count();
+        if output.len() >= options.stop_at {
+            stats.compressed_bytes = input_pos;
+            return Ok(stats);
+        }
+        let Some(mut offset) = get_control_bytes_within(&mut source, options.max_input)? else {
+            stats.compressed_bytes = input_pos;
+            return Ok(stats);
+        };
+
+        if options.strict_eof && member_token(offset) == Offset::EndOfMember {
+            if source.next_byte()?.is_some() {
+                return Err(DecompressError::TrailingData);
             }
-            Err(_) => {
-                break;
+            stats.compressed_bytes = source.count();
+            return Ok(stats);
+        }
+
+        if options.members && member_token(offset) == Offset::EndOfMember {
+            on_token(&Applied {
+                input_pos,
+                input_len: source.count(),
+                offset: Offset::EndOfMember,
+                output_len: output.len() - base,
+                output: &[],
+            });
+            base = output.len();
+            continue;
+        }
+
+        if let Some(check) = &mut canonical_check {
+            check.check(offset, input_pos)?;
+        }
+        match &mut offset {
+            Offset::Literal { .. } if options.forbid_literals => {
+                return Err(DecompressError::UnexpectedLiteral { input_pos });
+            }
+            Offset::Dictionary { length, offset } => {
+                check_offset(output.len() - base, *offset, input_pos)?;
+                *length = (*length).min(options.stop_at - output.len());
             }
+            _ => {}
         }
+        if output.len().saturating_add(offset.length()) > options.max_output {
+            return Err(DecompressError::OutputLimitExceeded {
+                limit: options.max_output,
+            });
+        }
+        let len = output.len();
+        output.apply(&mut source, offset, input_pos)?;
+        debug_assert!(output.len() > len, "token added no output");
+        stats.record(offset);
+        on_token(&Applied {
+            input_pos,
+            input_len: source.count(),
+            offset,
+            output_len: output.len() - base,
+            output: output.since(len),
+        });
+    }
+}
+
+/// Where `decode_with()` puts the output of each token.
+pub(crate) trait TokenOutput {
+    /// Number of bytes of output so far.
+    fn len(&self) -> usize;
+
+    /// Apply a token whose offset has already been checked, as `apply_token_at()` does.
+    fn apply<S: ByteSource>(
+        &mut self,
+        source: &mut S,
+        offset: Offset,
+        input_pos: usize,
+    ) -> Result<(), DecompressError>;
+
+    /// The output from index `start` on, or nothing if it is not kept.
+    fn since(&self, start: usize) -> &[u8];
+}
+
+impl TokenOutput for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
     }
 
-    Ok(dictionary)
+    fn apply<S: ByteSource>(
+        &mut self,
+        source: &mut S,
+        offset: Offset,
+        input_pos: usize,
+    ) -> Result<(), DecompressError> {
+        apply_token_at(source, self, offset, input_pos)
+    }
+
+    fn since(&self, start: usize) -> &[u8] {
+        &self[start..]
+    }
+}
+
+/// Output that is counted rather than kept, for walking a stream without decompressing it.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct OutputLen(usize);
+
+#[cfg(feature = "std")]
+impl TokenOutput for OutputLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+
+    fn apply<S: ByteSource>(
+        &mut self,
+        source: &mut S,
+        offset: Offset,
+        _input_pos: usize,
+    ) -> Result<(), DecompressError> {
+        match offset {
+            Offset::Literal { length: 0 } | Offset::Dictionary { length: 0, .. } => {
+                return Err(DecompressError::ZeroLength);
+            }
+            Offset::Literal { length } => {
+                let mut scratch = [0u8; crate::MAX_LITERAL_LEN];
+                source.read_exact_into(&mut scratch[..length])?;
+            }
+            _ => {}
+        }
+        self.0 += offset.length();
+        Ok(())
+    }
+
+    fn since(&self, _start: usize) -> &[u8] {
+        &[]
+    }
 }
 
-/// Fetch bytes from the decompression dictionary.
+/// Apply a single parsed token, appending its output to `dictionary`.
 ///
-/// Copies `length` bytes starting at `offset` in `dictionary` into a new Vec.
-/// Returns an error if offset or length are invalid.
-#[derive(Debug, PartialEq)]
-enum Offset {
+/// Literal tokens read their data from `source`; dictionary tokens copy from `dictionary` itself.
+/// A literal or dictionary token that would produce no output is rejected, so every applied token
+/// makes progress.
+///
+/// `input_pos` is the position of the token's control byte in the compressed stream, reported in
+/// errors.
+pub(crate) fn apply_token_at<S: ByteSource>(
+    source: &mut S,
+    dictionary: &mut Vec<u8>,
+    offset: Offset,
+    input_pos: usize,
+) -> Result<(), DecompressError> {
+    match offset {
+        Offset::Literal { length: 0 } | Offset::Dictionary { length: 0, .. } => {
+            return Err(DecompressError::ZeroLength);
+        }
+        Offset::Dictionary { length, offset } => {
+            fetch_offset(dictionary, length, offset, input_pos)?;
+        }
+        Offset::Literal { length } => {
+            // Read straight into the output, leaving it as it was if the source runs out.
+            let start = dictionary.len();
+            dictionary.resize(start + length, 0);
+            if let Err(err) = source.read_exact_into(&mut dictionary[start..]) {
+                dictionary.truncate(start);
+                return Err(err);
+            }
+        }
+        Offset::EndOfMember => {}
+    }
+
+    Ok(())
+}
+
+/// A single token of a compressed stream, as described by its control bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Offset {
     /// A literal data chunk of length `length` bytes.
     Literal { length: usize },
     /// A data chunk matching previously decompressed data.
     /// `length` bytes starting at `offset` in the decompression dictionary.
     Dictionary { length: usize, offset: usize },
+    /// The end of a member in a multi-member stream, see `decompress_members()`.
+    ///
+    /// Encoded as `END_OF_MEMBER`, and only recognised when decoding members.
+    EndOfMember,
+}
+
+impl Offset {
+    /// Number of decompressed bytes this token produces.
+    pub fn length(&self) -> usize {
+        match *self {
+            Offset::Literal { length } | Offset::Dictionary { length, .. } => length,
+            Offset::EndOfMember => 0,
+        }
+    }
+
+    /// Number of control bytes this token occupies in the compressed stream.
+    ///
+    /// A literal has a single control byte, followed by its `length` bytes of data which are not
+    /// counted here. A dictionary copy of up to 8 bytes takes two bytes, and a longer one three.
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            Offset::Literal { .. } => 1,
+            Offset::Dictionary { length, .. } if length < 9 => 2,
+            Offset::Dictionary { .. } | Offset::EndOfMember => 3,
+        }
+    }
 }
 
-/// Fetch a series of bytes from a the dictionary at a given offset
-fn fetch_offset(dictionary: &[u8], length: usize, offset: usize) -> Result<Vec<u8>, Error> {
-    if offset > dictionary.len() {
-        return Err("Offset larger than dictionary".into());
+/// Append a copy of `length` bytes from `offset` bytes back to the end of the dictionary.
+///
+/// The copy is made within the dictionary itself, without a temporary buffer. When it does not
+/// overlap the bytes it produces, it is a single `extend_from_within()` of the source range. When
+/// `length > offset`, a run of a single byte is filled directly, and longer patterns are copied in
+/// blocks that double in size each step, so there is no per-byte loop in either case.
+///
+/// The offset is validated before the dictionary grows, so a corrupt token claiming a long length
+/// cannot reserve memory for a copy that would fail.
+fn fetch_offset(
+    dictionary: &mut Vec<u8>,
+    length: usize,
+    offset: usize,
+    input_pos: usize,
+) -> Result<(), DecompressError> {
+    let start = check_offset(dictionary.len(), offset, input_pos)?;
+
+    if length <= offset {
+        dictionary.extend_from_within(start..start + length);
+        return Ok(());
     }
 
-    let mut result = Vec::with_capacity(length);
+    let end = dictionary.len() + length;
+    if offset == 1 {
+        let byte = dictionary[start];
+        dictionary.resize(end, byte);
+        return Ok(());
+    }
 
-    for i in 0..length {
-        let pos = dictionary.len() - offset + (i % offset);
-        if pos >= dictionary.len() {
-            return Err("Index out of bounds.".into());
-        }
-        result.push(dictionary[pos]);
+    // Each block starts a whole number of periods after `start`, so copying from `start` continues
+    // the pattern.
+    dictionary.reserve(length);
+    while dictionary.len() < end {
+        let copy = (end - dictionary.len()).min(dictionary.len() - start);
+        dictionary.extend_from_within(start..start + copy);
     }
 
-    Ok(result)
+    Ok(())
 }
 
-/// Read the next compressed data chunk's control bytes.
+/// Check that a dictionary `offset` can be resolved against `dictionary_len` bytes of history.
 ///
-/// Parses the 1-3 control bytes to determine the next Offset variant.
-fn get_control_bytes<R: Read>(reader: &mut R) -> Result<Offset, Error> {
-    let cb = read_u8(reader)?;
-    let q = q_mask(cb) as usize;
-    let cb_mask = cb_mask(cb) as usize;
+/// Returns the index the copy starts at, computed with a checked subtraction so an offset reaching
+/// before the start of the dictionary is an error rather than an underflow. `input_pos` is the
+/// position of the token's control byte, reported if the check fails.
+pub(crate) fn check_offset(
+    dictionary_len: usize,
+    offset: usize,
+    input_pos: usize,
+) -> Result<usize, DecompressError> {
+    if offset == 0 {
+        return Err(DecompressError::ZeroOffset);
+    }
 
-    Ok(match cb_mask {
-        1 => Offset::Literal { length: 1 + q },
-        3..=8 => {
-            let r = read_u8(reader)?;
-            Offset::Dictionary {
-                length: cb_mask,
-                offset: ((q << 8) + r as usize + 1),
-            }
+    dictionary_len
+        .checked_sub(offset)
+        .ok_or(DecompressError::OffsetTooLarge {
+            offset,
+            dictionary_len,
+            input_pos,
+        })
+}
+
+/// Check that a data stream from the reader decompresses, without keeping the output.
+///
+/// Walks every token, validating dictionary offsets against the running length of the output,
+/// but only tracks that length rather than the decompressed bytes themselves. Much cheaper than
+/// `decompress()` when only the integrity of a stream matters.
+///
+/// Returns the decompressed length, or the first error `decompress()` would have hit.
+#[cfg(feature = "std")]
+pub fn verify<R: Read>(reader: R) -> Result<usize, DecompressError> {
+    let mut output = OutputLen::default();
+    decode_with(
+        buffered(reader),
+        &mut output,
+        0,
+        DecodeOptions::default(),
+        |_| {},
+    )?;
+    Ok(output.0)
+}
+
+/// Check that a data stream from the reader decompresses to exactly `expected` bytes, without
+/// keeping the output.
+///
+/// A cheap integrity check against a length stored elsewhere, before committing to a full decode:
+/// the stream is walked as `verify()` walks it. Returns `Ok(false)` if it decodes to any other
+/// length, and the first error `decompress()` would have hit if it does not decode at all.
+#[cfg(feature = "std")]
+pub fn check_length<R: Read>(reader: R, expected: usize) -> Result<bool, DecompressError> {
+    Ok(verify(reader)? == expected)
+}
+
+/// Skip past a compressed stream in the reader without decompressing it.
+///
+/// Validates every token as `verify()` does, but returns the number of compressed bytes consumed
+/// rather than the decompressed length. The reader is not buffered internally, so it is left
+/// positioned exactly at the end of the stream, ready for whatever a container stores after it.
+#[cfg(feature = "std")]
+pub fn skip<R: Read>(reader: &mut R) -> Result<usize, DecompressError> {
+    let mut output = OutputLen::default();
+    let stats = decode_with(reader, &mut output, 0, DecodeOptions::default(), |_| {})?;
+    Ok(stats.compressed_bytes)
+}
+
+/// Parse the next token's control bytes from the reader.
+///
+/// Returns `None` at a clean end of stream. Literal data following a `Offset::Literal` token is
+/// left unread, so callers walking a stream must skip `length` bytes, or hand the token to
+/// `apply_token()`, before parsing the next token.
+#[cfg(feature = "std")]
+pub fn parse_control<R: Read>(reader: &mut R) -> Result<Option<Offset>, DecompressError> {
+    get_control_bytes(reader)
+}
+
+/// Apply a token parsed by `parse_control()`, appending its output to `output`.
+///
+/// A literal token reads its data from `reader`, and a dictionary token copies from `output`
+/// itself, with the same offset and length validation as `decompress()`. Together with
+/// `parse_control()` this lets callers run their own bookkeeping between tokens:
+///
+/// ```
+/// # fn main() -> Result<(), lz77::DecompressError> {
+/// let mut reader = std::io::Cursor::new([0x02, b'a', b'b', b'c', 0x20, 0x02]);
+/// let mut output = Vec::new();
+/// while let Some(token) = lz77::parse_control(&mut reader)? {
+///     lz77::apply_token(&mut output, &token, &mut reader)?;
+/// }
+/// assert_eq!(output, b"abcabc");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The token's position in the stream is not known here, so `OffsetTooLarge` errors report an
+/// `input_pos` of `0`.
+#[cfg(feature = "std")]
+pub fn apply_token<R: Read>(
+    output: &mut Vec<u8>,
+    token: &Offset,
+    reader: &mut R,
+) -> Result<(), DecompressError> {
+    apply_token_at(reader, output, *token, 0)
+}
+
+/// Iterate over the tokens of a compressed stream without decompressing it.
+///
+/// Literal data is skipped over rather than returned. The iterator ends at a clean end of stream,
+/// and yields a single `Err` and then stops if the stream is truncated part way through a token.
+#[cfg(feature = "std")]
+pub fn tokens<R: Read>(mut reader: R) -> impl Iterator<Item = Result<Offset, DecompressError>> {
+    let mut done = false;
+
+    core::iter::from_fn(move || {
+        if done {
+            return None;
         }
 
-        9 => {
-            let r = read_u8(reader)?;
-            let s = read_u8(reader)?;
+        let token = get_control_bytes(&mut reader).and_then(|token| {
+            if let Some(Offset::Literal { length }) = token {
+                reader.read_exact_into(&mut [0u8; crate::MAX_LITERAL_LEN][..length])?;
+            }
+            Ok(token)
+        });
 
-            Offset::Dictionary {
-                length: 9 + r as usize,
-                offset: ((q << 8) + s as usize + 1),
+        match token {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                done = true;
+                None
+            }
+            Err(e) => {
+                done = true;
+                Some(Err(e))
             }
         }
-        _ => unreachable!(),
     })
 }
 
-/// Bitmask the control byte to get the length variant code.
-fn cb_mask(i: u8) -> u8 {
-    if i | 0b0001_1111 == 0b0001_1111 {
-        return 1;
-    }
+/// Find the furthest back any dictionary token in a data stream from the reader reaches.
+///
+/// Returns the largest `offset` of any `Offset::Dictionary` token, or `0` for a stream of only
+/// literals. A `FixedDecompressor` with a window of at least this many bytes can decode the stream.
+///
+/// Like `tokens()`, this only parses the tokens: offsets are not checked against the output, so
+/// use `verify()` as well to find out whether the stream decodes.
+#[cfg(feature = "std")]
+pub fn max_offset_used<R: Read>(reader: R) -> Result<usize, DecompressError> {
+    tokens(BufReader::new(reader)).try_fold(0, |max, token| match token? {
+        Offset::Dictionary { offset, .. } => Ok(max.max(offset)),
+        _ => Ok(max),
+    })
+}
 
-    if i | 0b0011_1111 == 0b0011_1111 {
-        return 3;
-    }
+/// Read the next compressed data chunk's control bytes.
+///
+/// Parses the 1-3 control bytes to determine the next Offset variant.
+/// Returns `None` if the stream ends cleanly before a control byte, and an error if it ends
+/// part way through a token.
+pub(crate) fn get_control_bytes<S: ByteSource>(
+    source: &mut S,
+) -> Result<Option<Offset>, DecompressError> {
+    let cb = match source.next_byte()? {
+        Some(cb) => cb,
+        None => return Ok(None),
+    };
 
-    if i | 0b0101_1111 == 0b0101_1111 {
-        return 4;
-    }
+    let mut follow = [0u8; 2];
+    let follow = &mut follow[..follow_len(cb)];
+    source.read_exact_into(follow)?;
 
-    if i | 0b0111_1111 == 0b0111_1111 {
-        return 5;
-    }
+    parse_token(cb, follow).map(Some)
+}
 
-    if i | 0b1001_1111 == 0b1001_1111 {
-        return 6;
-    }
+/// Read the next token's control bytes as `get_control_bytes()` does, failing with
+/// `DecompressError::InputLimitExceeded` if the token would end past `max_input` bytes of input.
+///
+/// Each part of the token is checked before it is read, so neither its follow-up bytes nor a
+/// literal's data are read past the limit. Only at the limit itself is one more byte read, to tell
+/// a stream that ends there from one that goes on.
+fn get_control_bytes_within<S: ByteSource>(
+    source: &mut CountingSource<S>,
+    max_input: usize,
+) -> Result<Option<Offset>, DecompressError> {
+    let limit_exceeded = DecompressError::InputLimitExceeded { limit: max_input };
+    let input_pos = source.count();
+    let cb = match source.next_byte()? {
+        Some(_) if input_pos >= max_input => return Err(limit_exceeded),
+        Some(cb) => cb,
+        None => return Ok(None),
+    };
 
-    if i | 0b1011_1111 == 0b1011_1111 {
-        return 7;
+    let mut follow = [0u8; 2];
+    let follow = &mut follow[..follow_len(cb)];
+    if source.count() + follow.len() > max_input {
+        return Err(limit_exceeded);
     }
+    source.read_exact_into(follow)?;
 
-    if i | 0b1101_1111 == 0b1101_1111 {
-        return 8;
+    let offset = parse_token(cb, follow)?;
+    if let Offset::Literal { length } = offset {
+        if source.count() + length > max_input {
+            return Err(limit_exceeded);
+        }
     }
+    Ok(Some(offset))
+}
 
-    if i | 0b1111_1111 == 0b1111_1111 {
-        return 9;
+/// Number of bytes following control byte `cb` before the token's literal data, if any.
+pub(crate) fn follow_len(cb: u8) -> usize {
+    match cb_mask(cb) as usize {
+        1 => 0,
+        9 => 2,
+        _ => 1,
     }
+}
 
-    panic!("Unknown control byte. [{:08b}:{:02X}]", i, i);
+/// Build a token from its control byte and the `follow_len(cb)` bytes after it.
+///
+/// In both dictionary tiers the control byte's `q` bits are the high bits of `offset - 1` and the
+/// last follow-up byte is its low byte. The long tier puts its length byte `r` first, so it reads
+/// `[cb, r, s]` with the offset split between `cb` and `s`.
+pub(crate) fn parse_token(cb: u8, follow: &[u8]) -> Result<Offset, DecompressError> {
+    let q = q_mask(cb) as usize;
+    let tier = LengthTier::from_control_byte(cb);
+
+    Ok(match (tier, follow) {
+        (LengthTier::Literal, []) => Offset::Literal {
+            length: token_field(0, q as u8, 1)?,
+        },
+        (LengthTier::DictLong, &[r, s]) => Offset::Dictionary {
+            length: token_field(0, r, 9)?,
+            offset: token_field(q, s, 1)?,
+        },
+        // The discriminant of each single byte copy tier is its length.
+        (
+            LengthTier::Dict3
+            | LengthTier::Dict4
+            | LengthTier::Dict5
+            | LengthTier::Dict6
+            | LengthTier::Dict7
+            | LengthTier::Dict8,
+            &[r],
+        ) => Offset::Dictionary {
+            length: tier as usize,
+            offset: token_field(q, r, 1)?,
+        },
+        _ => return Err(DecompressError::UnknownControlByte(cb)),
+    })
 }
 
-/// Bitmask the control byte to get the dictionary offset code.
-fn q_mask(i: u8) -> u8 {
-    i & 0b0001_1111
+/// Compute `(high << 8) + low + base` for a token length or offset, without overflowing.
+///
+/// The fields of the current tiers all fit in 16 bits, but `usize` may be no wider than that.
+fn token_field(high: usize, low: u8, base: usize) -> Result<usize, DecompressError> {
+    high.checked_mul(1 << 8)
+        .and_then(|field| field.checked_add(low as usize))
+        .and_then(|field| field.checked_add(base))
+        .ok_or(DecompressError::MalformedHeader)
 }
 
-fn read_bytes(reader: &mut dyn Read, bytes: usize) -> Result<Vec<u8>, std::io::Error> {
-    let mut buf = vec![0u8; bytes];
-    reader.read_exact(&mut buf)?;
-    Ok(buf)
+/// Bitmask the control byte to get the length variant code, see `LengthTier`.
+fn cb_mask(i: u8) -> u8 {
+    ControlByte::new(i).tier() as u8
 }
 
-fn read_u8(reader: &mut dyn Read) -> Result<u8, std::io::Error> {
-    let mut buf = vec![0u8; 1];
-    reader.read_exact(&mut buf)?;
-    Ok(buf[0])
+/// Bitmask the control byte to get the dictionary offset code.
+fn q_mask(i: u8) -> u8 {
+    ControlByte::new(i).q_bits()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::{fs::File, io::Cursor};
 
     use super::*;
+    use crate::compress::TokenWriter;
+    use crate::source::SliceSource;
+    use crate::{MAX_LITERAL_LEN, MIN_MATCH_LEN};
 
     #[test]
     fn test_cb_mask() {
@@ -181,64 +1011,1191 @@ mod tests {
     }
 
     #[test]
-    fn test_get_control_bytes() -> Result<(), Error> {
+    fn test_get_control_bytes() -> Result<(), DecompressError> {
         use Offset::*;
 
         assert_eq!(
             get_control_bytes(&mut Cursor::new([0x02]))?,
-            Literal { length: 3 }
+            Some(Literal { length: 3 })
         );
 
         assert_eq!(
             get_control_bytes(&mut Cursor::new([0x20, 0x0E]))?,
-            Dictionary {
+            Some(Dictionary {
                 length: 3,
                 offset: 15
-            }
+            })
         );
 
         assert_eq!(
             get_control_bytes(&mut Cursor::new([0x60, 0x00]))?,
-            Dictionary {
+            Some(Dictionary {
                 length: 5,
                 offset: 1
-            }
+            })
         );
 
+        assert_eq!(get_control_bytes(&mut Cursor::new([]))?, None);
+
         Ok(())
     }
 
     #[test]
-    fn test_fetch_offset() {
+    fn test_follow_byte_order() -> Result<(), DecompressError> {
+        // q = 1 is the high byte of the offset and the last follow-up byte the low one, so
+        // swapping them would give an offset of 0x0201 + 1 instead.
         assert_eq!(
-            fetch_offset(&vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07], 3, 7).unwrap(),
-            vec![0x01, 0x02, 0x03]
+            get_control_bytes(&mut Cursor::new([0x21, 0x02]))?,
+            Some(Offset::Dictionary {
+                length: 3,
+                offset: 0x0102 + 1
+            })
         );
 
+        // In the long tier the length byte comes before the low offset byte.
         assert_eq!(
-            fetch_offset(&vec![0x01, 0x02, 0x03, 0xF4, 0x15, 0x06], 1, 5).unwrap(),
-            vec![0x02]
+            get_control_bytes(&mut Cursor::new([0xE1, 0x05, 0x02]))?,
+            Some(Offset::Dictionary {
+                length: 9 + 5,
+                offset: 0x0102 + 1
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_limits() -> Result<(), DecompressError> {
+        use crate::{MAX_LITERAL_LEN, MAX_MATCH_LEN, MAX_OFFSET};
+
+        assert_eq!(
+            get_control_bytes(&mut Cursor::new([0x1F]))?,
+            Some(Offset::Literal {
+                length: MAX_LITERAL_LEN
+            })
+        );
+        assert_eq!(
+            get_control_bytes(&mut Cursor::new([0xFF, 0xFF, 0xFF]))?,
+            Some(Offset::Dictionary {
+                length: MAX_MATCH_LEN,
+                offset: MAX_OFFSET
+            })
+        );
+        assert_eq!(
+            get_control_bytes(&mut Cursor::new([0x20, 0x00]))?,
+            Some(Offset::Dictionary {
+                length: MIN_MATCH_LEN,
+                offset: 1
+            })
         );
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_field() {
+        assert_eq!(token_field(0b1_1111, 0xFF, 1).unwrap(), crate::MAX_OFFSET);
+        assert_eq!(token_field(0, 0xFF, 9).unwrap(), crate::MAX_MATCH_LEN);
+        assert_eq!(token_field(0, 0b1_1111, 1).unwrap(), crate::MAX_LITERAL_LEN);
+        assert!(matches!(
+            token_field(usize::MAX >> 8, 0xFF, 1),
+            Err(DecompressError::MalformedHeader)
+        ));
+        assert!(matches!(
+            token_field(usize::MAX, 0, 0),
+            Err(DecompressError::MalformedHeader)
+        ));
+    }
+
+    #[test]
+    fn test_parse_control() -> Result<(), DecompressError> {
+        let mut reader = Cursor::new([0x01, 0xAB, 0xCD, 0xE0, 0x01, 0x01]);
 
         assert_eq!(
-            fetch_offset(&vec![0x00, 0x01, 0x00, 0x00, 0x00], 16, 4).unwrap(),
-            vec![
-                0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
-                0x00, 0x00
-            ]
+            parse_control(&mut reader)?,
+            Some(Offset::Literal { length: 2 })
         );
+        reader.set_position(3);
+        assert_eq!(
+            parse_control(&mut reader)?,
+            Some(Offset::Dictionary {
+                length: 10,
+                offset: 2
+            })
+        );
+        assert_eq!(parse_control(&mut reader)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens() -> Result<(), DecompressError> {
+        let tokens: Vec<Offset> = tokens(Cursor::new([
+            0x01, 0xAB, 0xCD, 0xE0, 0x01, 0x01, 0x20, 0x00,
+        ]))
+        .collect::<Result<_, _>>()?;
 
         assert_eq!(
-            fetch_offset(&vec![0x01, 0x02, 0xF4, 0x08, 0x00], 3, 1).unwrap(),
-            vec![0x00, 0x00, 0x00]
+            tokens,
+            vec![
+                Offset::Literal { length: 2 },
+                Offset::Dictionary {
+                    length: 10,
+                    offset: 2
+                },
+                Offset::Dictionary {
+                    length: 3,
+                    offset: 1
+                },
+            ]
         );
+        Ok(())
     }
 
     #[test]
-    fn test_deflate_file() -> Result<(), Error> {
-        Ok(assert_eq!(
-            decompress(File::open("tests/data/000.compressed")?)?,
-            std::fs::read("tests/data/000.decompressed")?,
-        ))
+    #[cfg(feature = "serde")]
+    fn test_offset_serde() -> Result<(), serde_json::Error> {
+        let tokens = [
+            (Offset::Literal { length: 3 }, r#"{"Literal":{"length":3}}"#),
+            (
+                Offset::Dictionary {
+                    length: 9,
+                    offset: 274,
+                },
+                r#"{"Dictionary":{"length":9,"offset":274}}"#,
+            ),
+            (Offset::EndOfMember, r#""EndOfMember""#),
+        ];
+
+        for (token, json) in tokens {
+            assert_eq!(serde_json::to_string(&token)?, json);
+            assert_eq!(serde_json::from_str::<Offset>(json)?, token);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_offset_used() -> Result<(), DecompressError> {
+        // "abc", a copy from 1 back and then one from 4 back.
+        let stream = [0x02, b'a', b'b', b'c', 0x20, 0x00, 0x20, 0x03];
+        assert_eq!(max_offset_used(Cursor::new(stream))?, 4);
+        assert!(crate::FixedDecompressor::<4>::new()
+            .decompress(&stream, |_| {})
+            .is_ok());
+        assert!(crate::FixedDecompressor::<3>::new()
+            .decompress(&stream, |_| {})
+            .is_err());
+
+        assert_eq!(max_offset_used(Cursor::new([0x01, b'a', b'b']))?, 0);
+        assert_eq!(max_offset_used(Cursor::new([]))?, 0);
+        assert_eq!(
+            max_offset_used(Cursor::new([0xFF, 0xFF, 0xFF]))?,
+            crate::MAX_OFFSET
+        );
+        assert!(matches!(
+            max_offset_used(Cursor::new([0x20])),
+            Err(DecompressError::UnexpectedEof { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_truncated() {
+        let mut iter = super::tokens(Cursor::new([0x00, 0x41, 0x02, 0x41]));
+        assert_eq!(iter.next().unwrap().unwrap(), Offset::Literal { length: 1 });
+        assert!(matches!(
+            iter.next(),
+            Some(Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            }))
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_tokens_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut total = 0;
+        for token in tokens(File::open("tests/data/000.compressed")?) {
+            total += token?.length();
+        }
+        assert_eq!(total, std::fs::read("tests/data/000.decompressed")?.len());
+        Ok(())
+    }
+
+    /// The bytes `fetch_offset()` appends to a copy of `dictionary`.
+    fn fetched(
+        dictionary: &[u8],
+        length: usize,
+        offset: usize,
+        input_pos: usize,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let mut output = dictionary.to_vec();
+        fetch_offset(&mut output, length, offset, input_pos)?;
+        Ok(output.split_off(dictionary.len()))
+    }
+
+    #[test]
+    fn test_fetch_offset() {
+        assert_eq!(
+            fetched(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07], 3, 7, 0).unwrap(),
+            vec![0x01, 0x02, 0x03]
+        );
+
+        assert_eq!(
+            fetched(&[0x01, 0x02, 0x03, 0xF4, 0x15, 0x06], 1, 5, 0).unwrap(),
+            vec![0x02]
+        );
+
+        assert_eq!(
+            fetched(&[0x00, 0x01, 0x00, 0x00, 0x00], 16, 4, 0).unwrap(),
+            vec![
+                0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
+                0x00, 0x00
+            ]
+        );
+
+        assert_eq!(
+            fetched(&[0x01, 0x02, 0xF4, 0x08, 0x00], 3, 1, 0).unwrap(),
+            vec![0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_fetch_offset_overlapping() -> Result<(), DecompressError> {
+        assert_eq!(
+            fetched(&[0xAB, 0xCD], 10, 2, 0)?,
+            vec![0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD]
+        );
+
+        assert_eq!(
+            fetched(&[0x00, 0x01, 0x02, 0x03], 7, 3, 0)?,
+            vec![0x01, 0x02, 0x03, 0x01, 0x02, 0x03, 0x01]
+        );
+
+        assert_eq!(fetched(&[0x05, 0x07], 300, 1, 0)?, vec![0x07; 300]);
+
+        let pattern = [0x01, 0x02, 0x03, 0x04, 0x05];
+        let expected: Vec<u8> = pattern.iter().copied().cycle().take(264).collect();
+        assert_eq!(fetched(&pattern, 264, 5, 0)?, expected);
+
+        assert_eq!(
+            decompress(Cursor::new([0x01, 0xAB, 0xCD, 0xE0, 0x01, 0x01]))?,
+            vec![0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD, 0xAB, 0xCD]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_offset_errors() {
+        assert!(matches!(
+            fetched(&[0x01, 0x02], 3, 3, 7),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 2,
+                input_pos: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn test_offset_error_input_pos() {
+        // The second dictionary token, at input byte 6, reaches back past the start.
+        let stream = [0x02, b'a', b'b', b'c', 0x20, 0x00, 0x20, 0x10];
+
+        for result in [decompress(Cursor::new(stream)), decompress_slice(&stream)] {
+            let err = result.unwrap_err();
+            assert!(matches!(
+                err,
+                DecompressError::OffsetTooLarge {
+                    offset: 17,
+                    dictionary_len: 6,
+                    input_pos: 6
+                }
+            ));
+            assert!(err.to_string().contains("at input byte 6"));
+        }
+    }
+
+    #[test]
+    fn test_check_offset_boundary() {
+        assert!(matches!(check_offset(4, 4, 0), Ok(0)));
+        assert!(matches!(check_offset(4, 1, 0), Ok(3)));
+        assert!(matches!(
+            check_offset(4, 5, 9),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 5,
+                dictionary_len: 4,
+                input_pos: 9
+            })
+        ));
+
+        // An offset of the whole dictionary copies from index 0, and one past the start fails
+        // cleanly instead of underflowing.
+        let dictionary = [0x01, 0x02, 0x03];
+        assert_eq!(fetched(&dictionary, 3, 3, 3).unwrap(), dictionary);
+        assert!(matches!(
+            fetched(&dictionary, 3, 4, 0),
+            Err(DecompressError::OffsetTooLarge { offset: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_decompress_all_literal() -> Result<(), DecompressError> {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut stream = Vec::new();
+        for chunk in data.chunks(MAX_LITERAL_LEN) {
+            stream.push(chunk.len() as u8 - 1);
+            stream.extend_from_slice(chunk);
+        }
+
+        assert_eq!(decompress(Cursor::new(&stream))?, data);
+        assert_eq!(decompress_slice(&stream)?, data);
+
+        // A literal cut short leaves the output as it was before the token.
+        let mut output = b"kept".to_vec();
+        assert!(matches!(
+            apply_token_at(
+                &mut SliceSource::new(&data[..10]),
+                &mut output,
+                Offset::Literal { length: 32 },
+                0
+            ),
+            Err(DecompressError::UnexpectedEof {
+                expected: 32,
+                got: 10
+            })
+        ));
+        assert_eq!(output, b"kept");
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_match_boundary() -> Result<(), DecompressError> {
+        // "a", then a run of `length` copies of it from tokens of the given lengths.
+        for (length, tokens) in [
+            (9, &[9][..]),
+            (263, &[263]),
+            (264, &[264]),
+            (265, &[262, 3]),
+        ] {
+            let stream = tokens
+                .iter()
+                .fold(TokenWriter::new().literal(b"a"), |writer, &token| {
+                    writer.dict(token, 1)
+                })
+                .finish();
+            assert_eq!(
+                decompress(Cursor::new(&stream))?,
+                vec![b'a'; 1 + length],
+                "length {}",
+                length
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_whole_output() -> Result<(), DecompressError> {
+        // "abc", then a copy of all 3 bytes so far from index 0, then the same for all 6.
+        let stream = TokenWriter::new()
+            .literal(b"abc")
+            .dict(3, 3)
+            .dict(6, 6)
+            .finish();
+        let expected = b"abcabcabcabc".to_vec();
+
+        assert_eq!(decompress(Cursor::new(&stream))?, expected);
+        assert_eq!(decompress_slice(&stream)?, expected);
+        assert!(matches!(
+            decompress_slice(&TokenWriter::new().literal(b"abc").dict(3, 4).finish()),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 4,
+                dictionary_len: 3,
+                input_pos: 4
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_offset_long_corrupt() {
+        // A 9-byte variant claiming 264 bytes from 3 back, against a 2 byte dictionary.
+        assert!(matches!(
+            decompress(Cursor::new([0x01, 0xAB, 0xCD, 0xE0, 0xFF, 0x02])),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 2,
+                input_pos: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fetch_offset_zero() {
+        assert!(matches!(
+            fetched(&[0x01, 0x02], 3, 0, 0),
+            Err(DecompressError::ZeroOffset)
+        ));
+        assert!(matches!(
+            fetched(&[], 0, 0, 0),
+            Err(DecompressError::ZeroOffset)
+        ));
+    }
+
+    #[test]
+    fn test_apply_token_zero_length() {
+        let mut dictionary = vec![0x01, 0x02];
+
+        assert!(matches!(
+            apply_token_at(
+                &mut Cursor::new([0xAA]),
+                &mut dictionary,
+                Offset::Literal { length: 0 },
+                0
+            ),
+            Err(DecompressError::ZeroLength)
+        ));
+        assert!(matches!(
+            apply_token_at(
+                &mut Cursor::new([]),
+                &mut dictionary,
+                Offset::Dictionary {
+                    length: 0,
+                    offset: 1
+                },
+                0
+            ),
+            Err(DecompressError::ZeroLength)
+        ));
+        assert_eq!(dictionary, vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_empty_input() -> Result<(), DecompressError> {
+        assert_eq!(decompress(Cursor::new([]))?, Vec::<u8>::new());
+        assert_eq!(decompress_slice(&[])?, Vec::<u8>::new());
+        assert_eq!(decompress_counted(Cursor::new([]))?, (Vec::new(), 0));
+
+        // A lone control byte is a truncated token, not an empty stream.
+        for (stream, expected) in [([0x00], 1), ([0x20], 1), ([0xE0], 2)] {
+            assert!(matches!(
+                decompress(Cursor::new(stream)),
+                Err(DecompressError::UnexpectedEof { expected: e, got: 0 }) if e == expected
+            ));
+            assert!(matches!(
+                decompress_slice(&stream),
+                Err(DecompressError::UnexpectedEof { expected: e, got: 0 }) if e == expected
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_make_progress() -> Result<(), DecompressError> {
+        // A literal and a 9 byte copy: every token adds at least one byte of output.
+        let (output, stats) = decompress_with_stats(Cursor::new([0x00, 0x41, 0xE0, 0x00, 0x00]))?;
+        assert_eq!(output, vec![0x41; 10]);
+        assert!(output.len() >= stats.literal_tokens + stats.dictionary_tokens);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_len() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(Offset::Literal { length: 32 }.encoded_len(), 1);
+        assert_eq!(
+            Offset::Dictionary {
+                length: 3,
+                offset: 1
+            }
+            .encoded_len(),
+            2
+        );
+        assert_eq!(
+            Offset::Dictionary {
+                length: 8,
+                offset: 8192
+            }
+            .encoded_len(),
+            2
+        );
+        assert_eq!(
+            Offset::Dictionary {
+                length: 9,
+                offset: 1
+            }
+            .encoded_len(),
+            3
+        );
+        assert_eq!(
+            Offset::EndOfMember.encoded_len(),
+            crate::END_OF_MEMBER.len()
+        );
+
+        // Control bytes plus literal data account for the whole stream.
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let mut total = 0;
+        for token in tokens(Cursor::new(&input)) {
+            let token = token?;
+            total += token.encoded_len();
+            if let Offset::Literal { length } = token {
+                total += length;
+            }
+        }
+        assert_eq!(total, input.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_tiers() -> Result<(), DecompressError> {
+        let prefix = b"abcdefgh";
+
+        // Each short tier copies its length from 8 bytes back: control `(n - 2) << 5`, r = 7.
+        for length in 3..=8u8 {
+            let mut stream = vec![0x07];
+            stream.extend_from_slice(prefix);
+            stream.extend_from_slice(&[(length - 2) << 5, 0x07]);
+
+            let mut expected = prefix.to_vec();
+            expected.extend_from_slice(&prefix[..length as usize]);
+            assert_eq!(
+                decompress(Cursor::new(&stream))?,
+                expected,
+                "tier {}",
+                length
+            );
+        }
+
+        // The 9-variant with the largest r, copying 9 + 255 bytes from 8 back.
+        let mut stream = vec![0x07];
+        stream.extend_from_slice(prefix);
+        stream.extend_from_slice(&[0xE0, 0xFF, 0x07]);
+        let expected: Vec<u8> = prefix.iter().copied().cycle().take(8 + 264).collect();
+        assert_eq!(decompress(Cursor::new(&stream))?, expected);
+
+        // A literal with the largest q, which is 31 for 32 bytes of data.
+        let data: Vec<u8> = (0..32).collect();
+        let mut stream = vec![0x1F];
+        stream.extend_from_slice(&data);
+        assert_eq!(decompress(Cursor::new(&stream))?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_token() -> Result<(), Box<dyn std::error::Error>> {
+        let mut reader = File::open("tests/data/000.compressed")?;
+        let mut output = Vec::new();
+        let mut tokens = 0;
+        while let Some(token) = parse_control(&mut reader)? {
+            apply_token(&mut output, &token, &mut reader)?;
+            tokens += 1;
+        }
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+        assert!(tokens > 1);
+
+        let mut output = b"ab".to_vec();
+        assert!(matches!(
+            apply_token(
+                &mut output,
+                &Offset::Dictionary {
+                    length: 3,
+                    offset: 3
+                },
+                &mut Cursor::new([])
+            ),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            apply_token(
+                &mut output,
+                &Offset::Literal { length: 0 },
+                &mut Cursor::new([])
+            ),
+            Err(DecompressError::ZeroLength)
+        ));
+        assert!(matches!(
+            apply_token(
+                &mut output,
+                &Offset::Literal { length: 2 },
+                &mut Cursor::new([b'c'])
+            ),
+            Err(DecompressError::UnexpectedEof {
+                expected: 2,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_literal() {
+        assert!(matches!(
+            decompress(Cursor::new([0x02, 0x01])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        assert!(matches!(
+            decompress_slice(&[0x1F, 0x01, 0x02]),
+            Err(DecompressError::UnexpectedEof {
+                expected: 32,
+                got: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_truncated_dictionary() {
+        assert!(matches!(
+            get_control_bytes(&mut Cursor::new([0x20])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 1,
+                got: 0
+            })
+        ));
+
+        assert!(matches!(
+            decompress(Cursor::new([0x00, 0x41, 0xE0, 0x01])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 2,
+                got: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decompress_with_capacity() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        let output =
+            decompress_with_capacity(File::open("tests/data/000.compressed")?, expected.len())?;
+        assert_eq!(output, expected);
+        assert_eq!(output.capacity(), expected.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_boxed() -> Result<(), Box<dyn std::error::Error>> {
+        let output = decompress_boxed(File::open("tests/data/000.compressed")?)?;
+        assert_eq!(
+            output[..],
+            std::fs::read("tests/data/000.decompressed")?[..]
+        );
+        assert!(decompress_boxed(Cursor::new([]))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_slice() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        assert_eq!(
+            decompress_slice(&input)?,
+            std::fs::read("tests/data/000.decompressed")?,
+        );
+        assert_eq!(decompress_slice(&input)?, decompress(Cursor::new(&input))?);
+        assert!(matches!(
+            decompress_slice(&[0x02, 0x01]),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_bytes() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        assert_eq!(
+            decompress_bytes(&input)?,
+            std::fs::read("tests/data/000.decompressed")?
+        );
+        assert_eq!(decompress_bytes("\x02abc\x60\x02")?, b"abcabcab".to_vec());
+        assert_eq!(decompress_bytes([0x00, 0x41])?, b"A".to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_framed() -> Result<(), Box<dyn std::error::Error>> {
+        let frame = |len: u32, stream: &[u8]| [&len.to_le_bytes()[..], stream].concat();
+        let stream = [0x02, b'a', b'b', b'c', 0x60, 0x02];
+
+        assert_eq!(
+            decompress_framed(Cursor::new(frame(8, &stream)))?,
+            b"abcabcab".to_vec()
+        );
+        assert!(decompress_framed(Cursor::new(frame(0, &[])))?.is_empty());
+
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        assert_eq!(
+            decompress_framed(Cursor::new(frame(expected.len() as u32, &input)))?,
+            expected
+        );
+
+        // The stream is longer than the header promises.
+        assert!(matches!(
+            decompress_framed(Cursor::new(frame(7, &stream))),
+            Err(DecompressError::OutputLimitExceeded { limit: 7 })
+        ));
+        // The stream is shorter than the header promises.
+        assert!(matches!(
+            decompress_framed(Cursor::new(frame(9, &stream))),
+            Err(DecompressError::LengthMismatch {
+                expected: 9,
+                actual: 8
+            })
+        ));
+        assert!(matches!(
+            decompress_framed(Cursor::new(frame(u32::MAX, &stream))),
+            Err(DecompressError::LengthMismatch { actual: 8, .. })
+        ));
+        assert!(matches!(
+            decompress_framed(Cursor::new([0x08, 0x00])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 4,
+                got: 2
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_append() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        let mut out = Vec::new();
+        assert_eq!(
+            decompress_append(Cursor::new(&input), &mut out)?,
+            expected.len()
+        );
+        assert_eq!(
+            decompress_append(Cursor::new([0x02, b'a', b'b', b'c', 0x60, 0x02]), &mut out)?,
+            8
+        );
+        assert_eq!(out[..expected.len()], expected);
+        assert_eq!(out[expected.len()..], *b"abcabcab");
+
+        // The second frame cannot reach back into the first.
+        let len = out.len();
+        assert!(matches!(
+            decompress_append(Cursor::new([0x00, b'x', 0x20, 0x01]), &mut out),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 2,
+                dictionary_len: 1,
+                input_pos: 2
+            })
+        ));
+        assert_eq!(out.len(), len);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_append_in_place() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        // A buffer recycled from a pool, with old contents cleared but its allocation kept.
+        let mut out = vec![0xAA; 2 * expected.len()];
+        out.clear();
+        let (ptr, capacity) = (out.as_ptr(), out.capacity());
+
+        decompress_append(Cursor::new(&input), &mut out)?;
+        assert_eq!(out, expected);
+        assert_eq!((out.as_ptr(), out.capacity()), (ptr, capacity));
+
+        // A failing stream leaves the allocation alone as well.
+        out.clear();
+        assert!(decompress_append(Cursor::new([0x1F, b'a']), &mut out).is_err());
+        assert!(out.is_empty());
+        assert_eq!((out.as_ptr(), out.capacity()), (ptr, capacity));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_iter() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        assert_eq!(
+            decompress_iter(input.into_iter())?,
+            std::fs::read("tests/data/000.decompressed")?
+        );
+        assert_eq!(
+            decompress_iter(b"\x02abc\x60\x02".iter().copied())?,
+            b"abcabcab".to_vec()
+        );
+        assert!(matches!(
+            decompress_iter([0x02, b'a']),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with_dictionary() -> Result<(), DecompressError> {
+        // "hello " copied from the preset, then a literal "you", then "hello" again.
+        let stream = [0x80, 0x05, 0x02, b'y', b'o', b'u', 0x60, 0x08];
+
+        assert_eq!(
+            decompress_with_dictionary(Cursor::new(stream), b"say hello ")?,
+            b"hello youhello".to_vec()
+        );
+        assert!(matches!(
+            decompress(Cursor::new(stream)),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        assert_eq!(
+            decompress_with_limit(File::open("tests/data/000.compressed")?, expected.len())?,
+            expected
+        );
+        assert!(matches!(
+            decompress_with_limit(File::open("tests/data/000.compressed")?, expected.len() - 1),
+            Err(DecompressError::OutputLimitExceeded { .. })
+        ));
+
+        // A single 264 byte run is rejected before it is copied.
+        assert!(matches!(
+            decompress_with_limit(Cursor::new([0x00, 0x41, 0xE0, 0xFF, 0x00]), 100),
+            Err(DecompressError::OutputLimitExceeded { limit: 100 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_prefix() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        for max_bytes in [0, 1, 100, 1000, expected.len(), expected.len() + 1] {
+            let mut reader = Cursor::new(&input);
+            let prefix = decompress_prefix(&mut reader, max_bytes)?;
+            assert_eq!(prefix, expected[..max_bytes.min(expected.len())]);
+
+            if max_bytes < expected.len() {
+                assert!(reader.position() < input.len() as u64);
+            }
+        }
+
+        // Cut part way through an overlapping copy.
+        assert_eq!(
+            decompress_prefix(Cursor::new([0x01, b'a', b'b', 0xE0, 0xFF, 0x01]), 7)?,
+            b"abababa".to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_prefix_cuts_copy() -> Result<(), DecompressError> {
+        // Runs of one byte and of a 3 byte pattern, each copied 100 bytes on from 5 of literal.
+        for offset in [1, 3] {
+            let stream = TokenWriter::new()
+                .literal(b"xyabc")
+                .dict(100, offset)
+                .finish();
+            let whole = decompress(Cursor::new(&stream))?;
+            assert_eq!(whole.len(), 105);
+
+            let prefix = decompress_prefix(Cursor::new(&stream), 37)?;
+            assert_eq!(prefix, whole[..37]);
+            // Only the bytes kept were copied, rather than the whole token and then truncated.
+            assert!(prefix.capacity() < whole.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_counted() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let (output, consumed) = decompress_counted(Cursor::new(&input))?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+        assert_eq!(consumed, input.len());
+
+        // Literal, 9-byte variant and short dictionary tokens: 3 + 3 + 2 bytes.
+        let (_, consumed) = decompress_counted(Cursor::new([
+            0x01, 0xAB, 0xCD, 0xE0, 0x01, 0x01, 0x20, 0x00,
+        ]))?;
+        assert_eq!(consumed, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with_stats() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let (output, stats) = decompress_with_stats(Cursor::new(&input))?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+        assert_eq!(stats.decompressed_bytes(), output.len());
+        assert_eq!(stats.compressed_bytes, input.len());
+        assert!(stats.ratio() > 1.0);
+
+        // A 2 byte literal, then a 9-byte variant and a short dictionary token.
+        let (_, stats) = decompress_with_stats(Cursor::new([
+            0x01, 0xAB, 0xCD, 0xE0, 0x01, 0x01, 0x20, 0x00,
+        ]))?;
+        assert_eq!(
+            stats,
+            DecompressStats {
+                literal_tokens: 1,
+                dictionary_tokens: 2,
+                literal_bytes: 2,
+                copied_bytes: 13,
+                compressed_bytes: 8,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with_progress() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let mut progress = Vec::new();
+        let output = decompress_with_progress(Cursor::new(&input), |consumed, produced| {
+            progress.push((consumed, produced))
+        })?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+
+        assert!(progress.len() > 1);
+        for pair in progress.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+        }
+        assert_eq!(progress.last(), Some(&(input.len(), output.len())));
+
+        let mut calls = 0;
+        decompress_with_progress(Cursor::new([]), |_, _| calls += 1)?;
+        assert_eq!(calls, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_and_digest() -> Result<(), Box<dyn std::error::Error>> {
+        use std::hash::DefaultHasher;
+
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let mut hasher = DefaultHasher::new();
+        let output = decompress_and_digest(Cursor::new(&input), &mut hasher)?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+
+        let mut expected = DefaultHasher::new();
+        expected.write(&output);
+        assert_eq!(hasher.finish(), expected.finish());
+
+        // A flipped literal byte still decodes, but to a different digest.
+        let mut corrupted = input.clone();
+        corrupted[1] ^= 0x01;
+        let mut hasher = DefaultHasher::new();
+        decompress_and_digest(Cursor::new(&corrupted), &mut hasher)?;
+        assert_ne!(hasher.finish(), expected.finish());
+
+        assert!(matches!(
+            decompress_and_digest(Cursor::new([0x20, 0x00]), &mut DefaultHasher::new()),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            verify(File::open("tests/data/000.compressed")?)?,
+            std::fs::read("tests/data/000.decompressed")?.len()
+        );
+        assert_eq!(verify(Cursor::new([]))?, 0);
+        assert!(matches!(
+            verify(Cursor::new([0x01, 0xAB, 0xCD, 0x20, 0x02])),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 2,
+                input_pos: 3
+            })
+        ));
+        assert!(matches!(
+            verify(Cursor::new([0x02, 0xAB])),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_length() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let len = std::fs::read("tests/data/000.decompressed")?.len();
+
+        assert!(check_length(Cursor::new(&input), len)?);
+        assert!(!check_length(Cursor::new(&input), len + 1)?);
+        assert!(!check_length(Cursor::new(&input), len - 1)?);
+        assert!(check_length(Cursor::new([]), 0)?);
+        assert!(check_length(
+            Cursor::new([0x02, b'a', b'b', b'c', 0x60, 0x02]),
+            8
+        )?);
+        assert!(matches!(
+            check_length(Cursor::new([0x00, b'a', 0x20, 0x01]), 4),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip() -> Result<(), Box<dyn std::error::Error>> {
+        let input = std::fs::read("tests/data/000.compressed")?;
+        let mut reader = Cursor::new(&input);
+        assert_eq!(skip(&mut reader)?, input.len());
+        assert_eq!(reader.position(), input.len() as u64);
+
+        // Only the framed stream is consumed from a reader limited to its length.
+        let mut container = Cursor::new([0x01, 0xAB, 0xCD, 0x20, 0x01, 0x42]);
+        assert_eq!(skip(&mut (&mut container).take(5))?, 5);
+        assert_eq!(container.position(), 5);
+
+        assert!(matches!(
+            skip(&mut Cursor::new([0x01, 0xAB, 0xCD, 0x20, 0x02])),
+            Err(DecompressError::OffsetTooLarge { input_pos: 3, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_members() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::{END_OF_MEMBER, MAX_MATCH_LEN, MAX_OFFSET};
+
+        // A run long enough that the terminator is also a valid dictionary reference.
+        let first = vec![b'a'; MAX_OFFSET + 100];
+        let second = b"xyzxyz".to_vec();
+
+        let mut stream = Vec::new();
+        crate::compress(&first, &mut stream)?;
+        stream.extend_from_slice(&END_OF_MEMBER);
+        stream.extend_from_slice(&[0x02, b'x', b'y', b'z', 0x20, 0x02]);
+        stream.extend_from_slice(&END_OF_MEMBER);
+
+        assert_eq!(
+            decompress_members(Cursor::new(&stream))?,
+            vec![first.clone(), second.clone()]
+        );
+
+        let single = decompress(Cursor::new(&stream))?;
+        assert_ne!(single, [first, second].concat());
+        assert_eq!(single.len(), MAX_OFFSET + 100 + 2 * MAX_MATCH_LEN + 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_members_isolated() -> Result<(), DecompressError> {
+        // The second member cannot reference the first.
+        let stream = [0x02, b'a', b'b', b'c', 0xFF, 0xFF, 0xFF, 0x20, 0x02];
+        assert!(matches!(
+            decompress_members(Cursor::new(stream)),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 0,
+                input_pos: 7
+            })
+        ));
+
+        assert_eq!(
+            decompress_members(Cursor::new([0x02, b'a', b'b', b'c']))?,
+            vec![b"abc".to_vec()]
+        );
+        assert!(decompress_members(Cursor::new([]))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_auto() -> Result<(), Box<dyn std::error::Error>> {
+        let compressed = std::fs::read("tests/data/000.compressed")?;
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+
+        // A single member, with and without a terminator.
+        assert_eq!(decompress_auto(Cursor::new(&compressed))?, expected);
+        let mut stream = compressed.clone();
+        stream.extend_from_slice(&crate::END_OF_MEMBER);
+        assert_eq!(decompress_auto(Cursor::new(&stream))?, expected);
+        assert!(decompress_auto(Cursor::new([]))?.is_empty());
+
+        // Further members after the terminator, the last unterminated.
+        stream.extend_from_slice(&[0x02, b'x', b'y', b'z', 0x20, 0x02]);
+        stream.extend_from_slice(&crate::END_OF_MEMBER);
+        stream.extend_from_slice(&compressed);
+        assert_eq!(
+            decompress_auto(Cursor::new(&stream))?,
+            [&expected[..], b"xyzxyz", &expected].concat()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_auto_isolated() {
+        // The second member cannot reference the first.
+        let stream = [0x02, b'a', b'b', b'c', 0xFF, 0xFF, 0xFF, 0x20, 0x02];
+        assert!(matches!(
+            decompress_auto(Cursor::new(stream)),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 0,
+                input_pos: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn test_deflate_file() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            decompress(File::open("tests/data/000.compressed")?)?,
+            std::fs::read("tests/data/000.decompressed")?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_buffered() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        let reader = BufReader::with_capacity(7, File::open("tests/data/000.compressed")?);
+        assert_eq!(decompress_buffered(reader)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_reads_in_blocks() -> Result<(), Box<dyn std::error::Error>> {
+        /// A reader that counts how many times it is called.
+        struct CountReads<R>(R, usize);
+
+        impl<R: Read> Read for CountReads<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.1 += 1;
+                self.0.read(buf)
+            }
+        }
+
+        let mut reader = CountReads(File::open("tests/data/000.compressed")?, 0);
+        decompress(&mut reader)?;
+        assert!(reader.1 <= 2, "{} reads", reader.1);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_slice() -> Result<(), DecompressError> {
+        assert_eq!(
+            decompress_slice(include_bytes!("../tests/data/000.compressed"))?,
+            include_bytes!("../tests/data/000.decompressed").to_vec()
+        );
+        Ok(())
     }
 }
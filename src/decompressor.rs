@@ -0,0 +1,460 @@
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::{Read, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use crate::decompress::{buffered, get_control_bytes};
+use crate::source::CountingSource;
+use crate::window::Window;
+use crate::{DecompressError, FixedDecompressor, MAX_OFFSET};
+
+/// A streaming decompressor that decodes lazily as it is read from.
+///
+/// Wraps a reader of compressed data and implements `Read` over the decompressed output,
+/// keeping only the sliding window needed to resolve dictionary references in memory rather than
+/// the full output.
+///
+/// Decompression errors are surfaced as `std::io::Error`s wrapping the `DecompressError`.
+///
+/// Control bytes are read from `reader` one at a time, and it is never read past the end of the
+/// stream, so wrap unbuffered readers such as a `File` in a `BufReader`.
+pub struct Decompressor<R: Read> {
+    reader: CountingSource<R>,
+    /// Recently decoded bytes, used as the dictionary for upcoming tokens.
+    window: Window<MAX_OFFSET>,
+    /// Output of the last token decoded.
+    decoded: Vec<u8>,
+    /// Position in `decoded` of the first byte not yet handed to the caller.
+    pos: usize,
+}
+
+impl<R: Read> Decompressor<R> {
+    /// Create a decompressor reading compressed data from `reader`.
+    pub fn new(reader: R) -> Self {
+        Decompressor {
+            reader: CountingSource::new(reader),
+            window: Window::new(),
+            decoded: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Start decompressing a new stream from `reader`, reusing this decompressor's buffers.
+    ///
+    /// Clears the sliding window and output buffer without releasing their memory, so decoding
+    /// many small streams through one `Decompressor` stops allocating once the output buffer has
+    /// grown to fit the longest token.
+    pub fn reset(&mut self, reader: R) {
+        self.reader = CountingSource::new(reader);
+        self.window.clear();
+        self.decoded.clear();
+        self.pos = 0;
+    }
+
+    /// Number of decompressed bytes already decoded and waiting to be read.
+    ///
+    /// `read()` hands these out before decoding another token, so once this is zero, no input
+    /// past the last decoded token has been consumed.
+    pub fn available(&self) -> usize {
+        self.decoded.len() - self.pos
+    }
+
+    /// Return the underlying reader, positioned just after the last token decoded.
+    ///
+    /// Control bytes and literal data are read exactly as needed, with no read-ahead, so the
+    /// reader holds everything after that token, such as data following the compressed stream.
+    /// Any of the token's output still `available()` is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Decode the next token into the window, replacing `decoded` with its output.
+    ///
+    /// Only called once every byte of the previous token has been handed out. Returns `false` once
+    /// the compressed stream has ended cleanly.
+    fn fill(&mut self) -> Result<bool, DecompressError> {
+        self.decoded.clear();
+        self.pos = 0;
+
+        let input_pos = self.reader.count();
+        let Some(offset) = get_control_bytes(&mut self.reader)? else {
+            return Ok(false);
+        };
+
+        let mut output = |bytes: &[u8]| {
+            self.decoded.extend_from_slice(bytes);
+            Ok(())
+        };
+        self.window
+            .apply_token(&mut self.reader, offset, input_pos, &mut output)?;
+        self.window.flush(&mut output)?;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.pos == self.decoded.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+
+        let available = &self.decoded[self.pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Decompress a data stream from the reader one byte at a time.
+///
+/// Decoding is lazy: the next token is read and decoded only once the bytes of the previous one
+/// have all been yielded, so a consumer that stops early never reads the rest of the stream. Like
+/// `Decompressor`, only the sliding window needed to resolve dictionary references is retained.
+///
+/// An error is yielded once, after which the iterator ends.
+///
+/// ```
+/// # use std::io::Cursor;
+/// let stream = [0x02, b'a', b'b', b'c', 0x60, 0x02];
+/// let head: Vec<u8> = lz77::decompress_bytes_iter(Cursor::new(stream))
+///     .take(4)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(head, b"abca");
+/// ```
+pub fn decompress_bytes_iter<R: Read>(
+    reader: R,
+) -> impl Iterator<Item = Result<u8, DecompressError>> {
+    let mut decompressor = Decompressor::new(reader);
+    let mut done = false;
+    core::iter::from_fn(move || {
+        while !done && decompressor.available() == 0 {
+            match decompressor.fill() {
+                Ok(true) => {}
+                Ok(false) => done = true,
+                Err(err) => {
+                    done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        if done {
+            return None;
+        }
+        let byte = decompressor.decoded[decompressor.pos];
+        decompressor.pos += 1;
+        Some(Ok(byte))
+    })
+}
+
+/// Decompress a data stream from the reader into the writer.
+///
+/// Unlike `decompress()`, the output is never held in memory in full. Decoding runs through a
+/// `FixedDecompressor` whose ring buffer holds exactly `MAX_OFFSET` bytes, the furthest back any
+/// dictionary token can reach, so memory use is constant however large the output grows. The
+/// reader is buffered internally, and decoded bytes are written out in window-sized batches.
+///
+/// A token reaching further back than the bytes retained is rejected with
+/// `DecompressError::OffsetTooLarge`, which for a well-formed stream only happens at its start.
+///
+/// Returns the total number of bytes written.
+pub fn decompress_into<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+) -> Result<u64, DecompressError> {
+    let total = FixedDecompressor::<MAX_OFFSET>::new()
+        .decode(buffered(reader), |bytes| Ok(writer.write_all(bytes)?))?;
+    Ok(total as u64)
+}
+
+/// Decompress a data stream from the reader into the file at `path`, creating or truncating it.
+///
+/// For extracting output too large to hold in memory: the stream is decoded through
+/// `decompress_into()`, keeping only the sliding window in memory, and written through a
+/// `BufWriter`.
+///
+/// On error the file is left holding whatever was written before it. Returns the number of bytes
+/// written. Requires the `fs` feature.
+#[cfg(feature = "fs")]
+pub fn decompress_to_file<R: Read, P: AsRef<Path>>(
+    reader: R,
+    path: P,
+) -> Result<u64, DecompressError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let written = decompress_into(reader, &mut writer)?;
+    writer.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Cursor};
+
+    use super::*;
+    use crate::compress::TokenWriter;
+
+    #[test]
+    fn test_decompressor_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        Decompressor::new(File::open("tests/data/000.compressed")?).read_to_end(&mut output)?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompressor_small_reads() -> Result<(), Box<dyn std::error::Error>> {
+        let mut decompressor = Decompressor::new(File::open("tests/data/000.compressed")?);
+        let mut output = Vec::new();
+        let mut buf = [0u8; 7];
+        loop {
+            let len = decompressor.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompressor_window_bounded() -> Result<(), Box<dyn std::error::Error>> {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let mut compressed = Vec::new();
+        crate::compress(&data, &mut compressed)?;
+
+        let mut decompressor = Decompressor::new(Cursor::new(compressed));
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let len = decompressor.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..len]);
+            assert!(decompressor.decoded.len() <= crate::MAX_MATCH_LEN);
+        }
+        assert_eq!(output, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompressor_reset() -> Result<(), Box<dyn std::error::Error>> {
+        let first = std::fs::read("tests/data/000.compressed")?;
+        let mut second = Vec::new();
+        crate::compress(b"a second, different stream", &mut second)?;
+
+        let mut decompressor = Decompressor::new(Cursor::new(first));
+        let mut output = Vec::new();
+        decompressor.read_to_end(&mut output)?;
+        assert_eq!(output, std::fs::read("tests/data/000.decompressed")?);
+
+        let capacity = decompressor.decoded.capacity();
+        decompressor.reset(Cursor::new(second));
+        assert_eq!(decompressor.available(), 0);
+        assert_eq!(decompressor.decoded.capacity(), capacity);
+
+        output.clear();
+        decompressor.read_to_end(&mut output)?;
+        assert_eq!(output, b"a second, different stream");
+        assert_eq!(decompressor.decoded.capacity(), capacity);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompressor_into_inner() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        let mut stream = std::fs::read("tests/data/000.compressed")?;
+        let compressed_len = stream.len();
+        stream.extend_from_slice(b"trailer");
+
+        let mut decompressor = Decompressor::new(Cursor::new(stream));
+        let mut output = vec![0; expected.len()];
+        decompressor.read_exact(&mut output[..10])?;
+        assert!(decompressor.available() > 0);
+        decompressor.read_exact(&mut output[10..])?;
+        assert_eq!(output, expected);
+        assert_eq!(decompressor.available(), 0);
+
+        let mut reader = decompressor.into_inner();
+        assert_eq!(reader.position(), compressed_len as u64);
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        assert_eq!(tail, b"trailer");
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_bytes_iter() -> Result<(), Box<dyn std::error::Error>> {
+        let compressed = std::fs::read("tests/data/000.compressed")?;
+        let output: Vec<u8> =
+            decompress_bytes_iter(Cursor::new(&compressed)).collect::<Result<_, _>>()?;
+        assert_eq!(output, crate::decompress(Cursor::new(&compressed))?);
+
+        let mut iter = decompress_bytes_iter(Cursor::new([0x00, b'a', 0x20, 0x05]));
+        assert!(matches!(iter.next(), Some(Ok(b'a'))));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(DecompressError::OffsetTooLarge { offset: 6, .. }))
+        ));
+        assert!(iter.next().is_none());
+        Ok(())
+    }
+
+    /// Counts the bytes read through it, visible after it has been moved into a decoder.
+    struct CountingReader<R> {
+        inner: R,
+        read: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.inner.read(buf)?;
+            self.read.set(self.read.get() + len);
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_decompress_bytes_iter_stops_early() -> Result<(), Box<dyn std::error::Error>> {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 253) as u8).collect();
+        let mut compressed = Vec::new();
+        crate::compress(&data, &mut compressed)?;
+
+        let read = std::rc::Rc::new(std::cell::Cell::new(0));
+        let reader = CountingReader {
+            inner: Cursor::new(&compressed),
+            read: read.clone(),
+        };
+        let head: Vec<u8> = decompress_bytes_iter(reader)
+            .take(100)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(head, data[..100]);
+        // Only the literals covering those bytes have been read, not the rest of the stream.
+        assert!(
+            read.get() < compressed.len() / 4,
+            "read {} bytes",
+            read.get()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_into() -> Result<(), Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        let written = decompress_into(File::open("tests/data/000.compressed")?, &mut output)?;
+        assert_eq!(
+            output,
+            crate::decompress(File::open("tests/data/000.compressed")?)?
+        );
+        assert_eq!(written, output.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_into_large() -> Result<(), Box<dyn std::error::Error>> {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let mut compressed = Vec::new();
+        crate::compress(&data, &mut compressed)?;
+
+        let mut output = Vec::new();
+        assert_eq!(
+            decompress_into(Cursor::new(compressed), &mut output)?,
+            data.len() as u64
+        );
+        assert_eq!(output, data);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_decompress_to_file() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("lz77-to-file-{}", std::process::id()));
+        let written = decompress_to_file(File::open("tests/data/000.compressed")?, &path);
+        let output = std::fs::read(&path);
+        std::fs::remove_file(&path)?;
+
+        let expected = std::fs::read("tests/data/000.decompressed")?;
+        assert_eq!(written?, expected.len() as u64);
+        assert_eq!(output?, expected);
+        Ok(())
+    }
+
+    /// Records the size of every write, to show output leaves the window in bounded batches.
+    struct BatchWriter {
+        output: Vec<u8>,
+        largest: usize,
+    }
+
+    impl Write for BatchWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.largest = self.largest.max(buf.len());
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_decompress_into_bounded() -> Result<(), Box<dyn std::error::Error>> {
+        let mut next = crate::test_util::xorshift(0x9E37_79B9);
+        let data: Vec<u8> = (0..40 * MAX_OFFSET)
+            .map(|i| {
+                let random = next();
+                if i % 3 == 0 {
+                    random as u8
+                } else {
+                    (i / 1000) as u8
+                }
+            })
+            .collect();
+        let mut compressed = Vec::new();
+        crate::compress_with_options(&data, &mut compressed, crate::CompressOptions::level(9))?;
+
+        let mut writer = BatchWriter {
+            output: Vec::new(),
+            largest: 0,
+        };
+        let written = decompress_into(Cursor::new(&compressed), &mut writer)?;
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(writer.output, crate::decompress(Cursor::new(&compressed))?);
+        assert_eq!(writer.largest, MAX_OFFSET);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_into_offset_too_large() {
+        // Copies from 3 bytes back after only 2 bytes of output.
+        let stream = TokenWriter::new().literal(b"ab").dict(3, 3).finish();
+        assert!(matches!(
+            decompress_into(Cursor::new(stream), Vec::new()),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 3,
+                dictionary_len: 2,
+                input_pos: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decompressor_error() {
+        let err = Decompressor::new(Cursor::new([0x20, 0x00]))
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
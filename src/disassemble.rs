@@ -0,0 +1,87 @@
+use std::fmt::Write;
+use std::io::Read;
+
+use crate::{tokens, DecompressError, Offset};
+
+/// Write a human readable listing of the tokens in a compressed stream.
+///
+/// Writes one line per token, prefixed with the position in the decompressed output the token
+/// starts at:
+///
+/// ```text
+/// 00000000 LIT len=3
+/// 00000003 DICT len=9 off=274
+/// ```
+///
+/// The format is kept stable so listings can be compared against golden snapshots.
+pub fn disassemble<R: Read>(reader: R, out: &mut dyn Write) -> Result<(), DecompressError> {
+    let mut position = 0;
+
+    for token in tokens(reader) {
+        let token = token?;
+        match token {
+            Offset::Literal { length } => writeln!(out, "{:08} LIT len={}", position, length),
+            Offset::Dictionary { length, offset } => {
+                writeln!(out, "{:08} DICT len={} off={}", position, length, offset)
+            }
+            Offset::EndOfMember => writeln!(out, "{:08} END", position),
+        }
+        .map_err(|_| DecompressError::Io(std::io::Error::other("failed to write disassembly")))?;
+
+        position += token.length();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_disassemble() -> Result<(), DecompressError> {
+        let mut listing = String::new();
+        disassemble(
+            Cursor::new([0x02, b'a', b'b', b'c', 0xE1, 0x00, 0x11, 0x20, 0x00]),
+            &mut listing,
+        )?;
+
+        assert_eq!(
+            listing,
+            "00000000 LIT len=3\n00000003 DICT len=9 off=274\n00000012 DICT len=3 off=1\n"
+        );
+        Ok(())
+    }
+
+    /// Any change to how tokens are parsed shows up here as a changed line in the listing.
+    #[test]
+    fn test_disassemble_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+        let mut listing = String::new();
+        disassemble(
+            std::fs::File::open("tests/data/000.compressed")?,
+            &mut listing,
+        )?;
+        let snapshot = std::fs::read_to_string("tests/data/000.tokens.txt")?;
+
+        for (line, (got, expected)) in listing.lines().zip(snapshot.lines()).enumerate() {
+            assert_eq!(got, expected, "line {} of the listing", line + 1);
+        }
+        assert_eq!(listing.lines().count(), snapshot.lines().count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_disassemble_truncated() {
+        let mut listing = String::new();
+        assert!(matches!(
+            disassemble(Cursor::new([0x00, b'a', 0x20]), &mut listing),
+            Err(DecompressError::UnexpectedEof {
+                expected: 1,
+                got: 0
+            })
+        ));
+        assert_eq!(listing, "00000000 LIT len=1\n");
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::TraceEntry;
+
+/// Error type returned by decompress() and helper methods.
+#[derive(Debug)]
+pub enum DecompressError {
+    /// A dictionary token referenced further back than the data decompressed so far.
+    ///
+    /// `input_pos` is the index in the compressed stream of the token's control byte.
+    OffsetTooLarge {
+        offset: usize,
+        dictionary_len: usize,
+        input_pos: usize,
+    },
+    /// A dictionary token referenced an offset of zero, which would copy from itself.
+    ZeroOffset,
+    /// A literal or dictionary token decoded to a length of zero.
+    ZeroLength,
+    /// A control byte did not match any known length variant.
+    UnknownControlByte(u8),
+    /// A token's length or offset overflowed while being decoded from its control bytes.
+    MalformedHeader,
+    /// Decompressing the next token would exceed the caller's output limit.
+    OutputLimitExceeded { limit: usize },
+    /// Decompressing the next token would read past the caller's input limit, see
+    /// `Decompress::max_input()`.
+    InputLimitExceeded { limit: usize },
+    /// A frame decoded to fewer bytes than its header promised, see `decompress_framed()`.
+    LengthMismatch { expected: usize, actual: usize },
+    /// The compressed stream ended before the current token was complete.
+    ///
+    /// `expected` is the number of bytes the token still needed when it was cut short, either its
+    /// follow-up bytes or its literal data, and `got` is how many of them were read before the end.
+    UnexpectedEof { expected: usize, got: usize },
+    /// A token was split differently from how `compress()` would have split it, rejected when
+    /// decoding with `Decompress::canonical()`.
+    ///
+    /// `input_pos` is the index in the compressed stream of the token's control byte.
+    NonCanonical { input_pos: usize },
+    /// A literal token appeared in a stream decoded with `Decompress::forbid_literals()`.
+    ///
+    /// `input_pos` is the index in the compressed stream of the token's control byte.
+    UnexpectedLiteral { input_pos: usize },
+    /// An option set on `Decompress` is not supported by the method it was decoded with, such as
+    /// `recover()` with any method but `from_slice_recovering()`.
+    UnsupportedOption { option: &'static str },
+    /// Input continued past the `END_OF_MEMBER` terminator of a stream decoded with
+    /// `Decompress::strict_eof()`.
+    TrailingData,
+    /// An error from a stream decoded with `Decompress::trace()`, along with the last tokens
+    /// applied before it, oldest first.
+    Traced {
+        error: Box<DecompressError>,
+        trace: Vec<TraceEntry>,
+    },
+    /// The underlying reader returned an error.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecompressError::OffsetTooLarge {
+                offset,
+                dictionary_len,
+                input_pos,
+            } => write!(
+                f,
+                "Offset larger than dictionary at input byte {}. [offset {} > {} bytes]",
+                input_pos, offset, dictionary_len
+            ),
+            DecompressError::ZeroOffset => write!(f, "Dictionary offset of zero."),
+            DecompressError::ZeroLength => write!(f, "Token length of zero."),
+            DecompressError::UnknownControlByte(cb) => {
+                write!(f, "Unknown control byte. [{:08b}:{:02X}]", cb, cb)
+            }
+            DecompressError::MalformedHeader => {
+                write!(f, "Token length or offset overflowed.")
+            }
+            DecompressError::OutputLimitExceeded { limit } => {
+                write!(f, "Decompressed output exceeds limit of {} bytes.", limit)
+            }
+            DecompressError::InputLimitExceeded { limit } => {
+                write!(f, "Compressed input exceeds limit of {} bytes.", limit)
+            }
+            DecompressError::LengthMismatch { expected, actual } => write!(
+                f,
+                "Decompressed {} bytes but the frame header promised {}.",
+                actual, expected
+            ),
+            DecompressError::UnexpectedEof { expected, got } => {
+                write!(
+                    f,
+                    "Reached end of compressed buffer in the middle of a token. [read {} of {} bytes]",
+                    got, expected
+                )
+            }
+            DecompressError::NonCanonical { input_pos } => {
+                write!(f, "Non-canonical token at input byte {}.", input_pos)
+            }
+            DecompressError::UnexpectedLiteral { input_pos } => {
+                write!(
+                    f,
+                    "Literal token at input byte {} where none are allowed.",
+                    input_pos
+                )
+            }
+            DecompressError::UnsupportedOption { option } => {
+                write!(f, "Option `{}` is not supported by this method.", option)
+            }
+            DecompressError::TrailingData => {
+                write!(f, "Unexpected data after the end of the compressed stream.")
+            }
+            DecompressError::Traced { error, trace } => {
+                write!(f, "{} [after {} traced tokens]", error, trace.len())
+            }
+            #[cfg(feature = "std")]
+            DecompressError::Io(err) => write!(f, "IO error: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecompressError::Io(err) => Some(err),
+            DecompressError::Traced { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for DecompressError {
+    fn from(err: std::io::Error) -> Self {
+        DecompressError::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DecompressError> for std::io::Error {
+    fn from(err: DecompressError) -> Self {
+        match err {
+            DecompressError::Io(err) => err,
+            DecompressError::UnexpectedEof { .. } => {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, err)
+            }
+            DecompressError::Traced { ref error, .. }
+                if matches!(**error, DecompressError::UnexpectedEof { .. }) =>
+            {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, err)
+            }
+            _ => std::io::Error::new(std::io::ErrorKind::InvalidData, err),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::decompress::get_control_bytes;
+use crate::source::{ByteSource, CountingSource, SliceSource};
+use crate::window::Window;
+use crate::DecompressError;
+
+/// A decompressor whose sliding window is a fixed `W` byte ring buffer, for targets without a heap.
+///
+/// Decoded bytes are handed to a callback as they are produced instead of being collected, and
+/// only the last `W` of them are kept to resolve dictionary references. A stream referencing
+/// further back than `W` bytes is rejected with `DecompressError::OffsetTooLarge`, so `W` should
+/// be `MAX_OFFSET` to decode any stream, or smaller when the encoder is known to stay within it.
+///
+/// Never allocates.
+pub struct FixedDecompressor<const W: usize> {
+    window: Window<W>,
+}
+
+impl<const W: usize> Default for FixedDecompressor<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize> FixedDecompressor<W> {
+    /// Create a decompressor with an empty window.
+    pub const fn new() -> Self {
+        FixedDecompressor {
+            window: Window::new(),
+        }
+    }
+
+    /// Decompress an in-memory data stream, passing the output to `output` in order.
+    ///
+    /// The window is cleared first, so each call decodes an independent stream. `output` is called
+    /// with batches of at most `W` bytes each time the window fills, and once more with whatever
+    /// remains at the end of the stream.
+    ///
+    /// Returns the total number of bytes decoded.
+    pub fn decompress<F: FnMut(&[u8])>(
+        &mut self,
+        input: &[u8],
+        mut output: F,
+    ) -> Result<usize, DecompressError> {
+        self.decode(SliceSource::new(input), |bytes| {
+            output(bytes);
+            Ok(())
+        })
+    }
+
+    /// Decompress a data stream from any byte source, as `decompress()` does for a slice.
+    ///
+    /// An error returned by `output` stops decoding and is passed straight back to the caller.
+    pub(crate) fn decode<S, F>(
+        &mut self,
+        source: S,
+        mut output: F,
+    ) -> Result<usize, DecompressError>
+    where
+        S: ByteSource,
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        let mut source = CountingSource::new(source);
+        let mut total = 0;
+
+        self.window.clear();
+
+        loop {
+            let input_pos = source.count();
+            let Some(offset) = get_control_bytes(&mut source)? else {
+                self.window.flush(&mut output)?;
+                return Ok(total);
+            };
+
+            self.window
+                .apply_token(&mut source, offset, input_pos, &mut output)?;
+            total += offset.length();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::decompress_slice;
+
+    fn decode<const W: usize>(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        let mut output = Vec::new();
+        let total = FixedDecompressor::<W>::new()
+            .decompress(input, |bytes| output.extend_from_slice(bytes))?;
+        assert_eq!(total, output.len());
+        Ok(output)
+    }
+
+    #[test]
+    fn test_fixed_decompressor() -> Result<(), DecompressError> {
+        // "abcd", then a 264 byte run copying it, then a 3 byte copy from 16 back.
+        let stream = [0x03, b'a', b'b', b'c', b'd', 0xE0, 0xFF, 0x03, 0x20, 0x0F];
+        let expected = decompress_slice(&stream)?;
+
+        assert_eq!(decode::<16>(&stream)?, expected);
+        assert_eq!(decode::<7>(&stream[..8])?, expected[..268]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_decompressor_file() -> Result<(), DecompressError> {
+        let input = include_bytes!("../tests/data/000.compressed");
+        assert_eq!(
+            decode::<{ crate::MAX_OFFSET }>(input)?,
+            include_bytes!("../tests/data/000.decompressed").to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_decompressor_window_exceeded() {
+        // The copy from 16 back no longer fits in an 8 byte window.
+        let stream = [0x03, b'a', b'b', b'c', b'd', 0xE0, 0xFF, 0x03, 0x20, 0x0F];
+        assert!(matches!(
+            decode::<8>(&stream),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 16,
+                dictionary_len: 8,
+                input_pos: 8
+            })
+        ));
+        assert!(matches!(
+            decode::<8>(&[0x20, 0x00]),
+            Err(DecompressError::OffsetTooLarge {
+                dictionary_len: 0,
+                ..
+            })
+        ));
+    }
+}
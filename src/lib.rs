@@ -1,5 +1,98 @@
 //! LZ77 is a lossless sliding window data compression algorithm. It replaces repeated occurrences of data with references to a single copy.
+//!
+//! Compressed data already in memory is best decoded with `decompress_bytes()`, while
+//! `decompress()` reads from any `std::io::Read`.
+//!
+//! The crate is `no_std` compatible with the default `std` feature disabled, needing only `alloc`.
+//! Decoding from memory or a custom source still works: `decompress_bytes()`,
+//! `decompress_slice()`, `decompress_iter()`, the `Decompress` builder through `from_slice()`,
+//! `read_iter()` and `read_source()` with any `ByteSource`, `StreamingDecoder` for input arriving
+//! in chunks, and the heap-free `FixedDecompressor`. Everything built on `std::io`, including
+//! `decompress()` and the encoder behind `compress()`, needs `std`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod builder;
+mod canonical;
+#[cfg(feature = "std")]
+mod compress;
+#[cfg(feature = "std")]
+mod compressor;
+mod control;
 mod decompress;
+#[cfg(feature = "std")]
+mod decompressor;
+#[cfg(feature = "std")]
+mod disassemble;
+mod error;
+mod fixed;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod recover;
+mod source;
+mod stats;
+mod streaming;
+#[cfg(all(test, feature = "std"))]
+mod test_util;
+mod trace;
+mod window;
+
+#[cfg(feature = "tokio")]
+pub use asynchronous::decompress_async;
+pub use builder::Decompress;
+#[cfg(feature = "std")]
+pub use compress::{compress, compress_bound, compress_with_options, CompressOptions};
+#[cfg(feature = "std")]
+pub use compressor::Compressor;
+pub use control::{ControlByte, ControlSpec, LengthTier, CONTROL_FORMAT};
+#[cfg(feature = "std")]
+pub use decompress::{
+    apply_token, check_length, decompress, decompress_and_digest, decompress_append,
+    decompress_auto, decompress_boxed, decompress_buffered, decompress_counted, decompress_framed,
+    decompress_members, decompress_prefix, decompress_with_capacity, decompress_with_dictionary,
+    decompress_with_limit, decompress_with_progress, decompress_with_stats, max_offset_used,
+    parse_control, skip, tokens, verify,
+};
+pub use decompress::{decompress_bytes, decompress_iter, decompress_slice, Offset};
+#[cfg(feature = "fs")]
+pub use decompressor::decompress_to_file;
+#[cfg(feature = "std")]
+pub use decompressor::{decompress_bytes_iter, decompress_into, Decompressor};
+#[cfg(feature = "std")]
+pub use disassemble::disassemble;
+pub use error::DecompressError;
+pub use fixed::FixedDecompressor;
+#[cfg(feature = "mmap")]
+pub use mmap::decompress_file;
+#[cfg(feature = "rayon")]
+pub use parallel::decompress_members_parallel;
+pub use recover::{Recovered, Skipped};
+pub use source::ByteSource;
+pub use stats::DecompressStats;
+pub use streaming::StreamingDecoder;
+pub use trace::{TraceEntry, TRACE_LEN};
+
+/// Furthest back a dictionary token can reference, `(0b1_1111 << 8) + 255 + 1`.
+///
+/// This is also the amount of history a decoder must retain to resolve any dictionary token.
+pub const MAX_OFFSET: usize = (0b1_1111 << 8) + 255 + 1;
+
+/// Longest output a single dictionary token can encode, `9 + 255`.
+pub const MAX_MATCH_LEN: usize = 9 + 255;
+
+/// Shortest output a dictionary token can encode.
+pub const MIN_MATCH_LEN: usize = 3;
+
+/// Longest run of bytes a single literal token can hold, `1 + 0b1_1111`.
+pub const MAX_LITERAL_LEN: usize = 1 + 0b1_1111;
 
-pub use decompress::decompress;
+/// Control bytes terminating a member of a multi-member stream, see `decompress_members()`.
+///
+/// Outside of member decoding these bytes are an ordinary `MAX_MATCH_LEN` copy at `MAX_OFFSET`.
+pub const END_OF_MEMBER: [u8; 3] = [0xFF, 0xFF, 0xFF];
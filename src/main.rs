@@ -0,0 +1,169 @@
+//! Command line front end for the `lz77` crate.
+//!
+//! ```text
+//! lz77 -c [-1..-9] [FILE] > out.compressed
+//! lz77 -d [FILE] > out
+//! ```
+//!
+//! Reads from `FILE`, or stdin when it is missing or `-`, and writes to stdout.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use lz77::{CompressOptions, DecompressError};
+
+const USAGE: &str = "\
+Usage: lz77 (-c | -d) [-1..-9] [FILE]
+
+Compress or decompress FILE, or stdin, to stdout.
+
+Options:
+  -c        Compress
+  -d        Decompress
+  -1..-9    Compression level, from fastest to smallest (default 6)
+  -h        Print this help";
+
+/// Exit status for invalid command line arguments, as in `sysexits.h`.
+const EXIT_USAGE: u8 = 64;
+/// Exit status for a malformed compressed stream.
+const EXIT_DATA: u8 = 65;
+/// Exit status for an input file that could not be opened.
+const EXIT_NO_INPUT: u8 = 66;
+/// Exit status for a failure reading or writing data.
+const EXIT_IO: u8 = 74;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Compress,
+    Decompress,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    mode: Mode,
+    level: u32,
+    input: Option<String>,
+}
+
+/// Parse the arguments following the program name.
+///
+/// Returns `Ok(None)` when help was requested.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Args>, String> {
+    let mut mode = None;
+    let mut level = 6;
+    let mut input = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-c" => mode = Some(Mode::Compress),
+            "-d" => mode = Some(Mode::Decompress),
+            "-" => input = Some(arg),
+            flag if flag.starts_with('-') => match flag[1..].parse() {
+                Ok(n @ 1..=9) => level = n,
+                _ => return Err(format!("unknown option '{}'", flag)),
+            },
+            _ if input.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+            _ => input = Some(arg),
+        }
+    }
+
+    let mode = mode.ok_or("one of -c or -d is required")?;
+    let input = input.filter(|path| path != "-");
+
+    Ok(Some(Args { mode, level, input }))
+}
+
+fn run(args: Args) -> Result<(), (u8, String)> {
+    let input: Box<dyn Read> = match &args.input {
+        Some(path) => {
+            Box::new(File::open(path).map_err(|err| (EXIT_NO_INPUT, format!("{}: {}", path, err)))?)
+        }
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut output = BufWriter::new(io::stdout().lock());
+
+    match args.mode {
+        Mode::Compress => {
+            let mut data = Vec::new();
+            BufReader::new(input)
+                .read_to_end(&mut data)
+                .map_err(io_error)?;
+            lz77::compress_with_options(&data, &mut output, CompressOptions::level(args.level))
+                .map_err(io_error)?;
+        }
+        Mode::Decompress => {
+            lz77::decompress_into(BufReader::new(input), &mut output).map_err(|err| match err {
+                DecompressError::Io(err) => io_error(err),
+                err => (EXIT_DATA, err.to_string()),
+            })?;
+        }
+    }
+
+    output.flush().map_err(io_error)
+}
+
+fn io_error(err: io::Error) -> (u8, String) {
+    (EXIT_IO, err.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("lz77: {}\n\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err((code, message)) => {
+            eprintln!("lz77: {}", message);
+            ExitCode::from(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse(&["-c", "-9", "in.bin"]),
+            Ok(Some(Args {
+                mode: Mode::Compress,
+                level: 9,
+                input: Some("in.bin".into()),
+            }))
+        );
+        assert_eq!(
+            parse(&["-d", "-"]),
+            Ok(Some(Args {
+                mode: Mode::Decompress,
+                level: 6,
+                input: None,
+            }))
+        );
+        assert_eq!(parse(&["-h"]), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_args_invalid() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["-c", "-0"]).is_err());
+        assert!(parse(&["-c", "-x"]).is_err());
+        assert!(parse(&["-d", "a", "b"]).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{decompress_slice, DecompressError};
+
+/// Decompress a file by memory-mapping it.
+///
+/// Maps the file at `path` and decodes the mapping in place with `decompress_slice()`, rather than
+/// copying it through a `Read`er. Requires the `mmap` feature.
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process, until the call
+/// returns. The mapping is read as an ordinary `&[u8]`, so a concurrent change is undefined
+/// behaviour, and truncation can crash the process with `SIGBUS`.
+pub unsafe fn decompress_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, DecompressError> {
+    let file = File::open(path)?;
+
+    // SAFETY: the caller guarantees the file is left unmodified until this returns, and the
+    // mapping is dropped before then.
+    let map = unsafe { Mmap::map(&file)? };
+
+    decompress_slice(&map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_file() -> Result<(), Box<dyn std::error::Error>> {
+        // SAFETY: the test data is never written to.
+        assert_eq!(
+            unsafe { decompress_file("tests/data/000.compressed")? },
+            std::fs::read("tests/data/000.decompressed")?
+        );
+        assert!(matches!(
+            unsafe { decompress_file("tests/data/missing") },
+            Err(DecompressError::Io(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::decompress::{get_control_bytes, member_token};
+use crate::source::{ByteSource, CountingSource, SliceSource};
+use crate::{decompress_slice, DecompressError, Offset, MAX_LITERAL_LEN};
+
+/// Decompress the members of an in-memory multi-member stream in parallel.
+///
+/// Produces the same members as `decompress_members()`. The member boundaries are found with a
+/// quick sequential walk over the control bytes, then each member is decoded on the `rayon` thread
+/// pool with `decompress_slice()`. Requires the `rayon` feature.
+///
+/// If several members are corrupt, the error for the earliest one is returned.
+pub fn decompress_members_parallel(input: &[u8]) -> Result<Vec<Vec<u8>>, DecompressError> {
+    let members: Vec<_> = member_ranges(input)?
+        .into_par_iter()
+        .map(|range| decompress_slice(&input[range]))
+        .collect();
+
+    members.into_iter().collect()
+}
+
+/// Find the byte range of each member in `input`, excluding its `END_OF_MEMBER` terminator.
+///
+/// Only token lengths are walked; literal data is skipped and offsets are left for the decoder to
+/// check.
+fn member_ranges(input: &[u8]) -> Result<Vec<Range<usize>>, DecompressError> {
+    let mut source = CountingSource::new(SliceSource::new(input));
+    let mut scratch = [0u8; MAX_LITERAL_LEN];
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    loop {
+        let token_start = source.count();
+        let Some(offset) = get_control_bytes(&mut source)? else {
+            break;
+        };
+
+        match member_token(offset) {
+            Offset::EndOfMember => {
+                ranges.push(start..token_start);
+                start = source.count();
+            }
+            Offset::Literal { length } => source.read_exact_into(&mut scratch[..length])?,
+            Offset::Dictionary { .. } => {}
+        }
+    }
+
+    if start < input.len() {
+        ranges.push(start..input.len());
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{compress, decompress_members, END_OF_MEMBER};
+
+    #[test]
+    fn test_decompress_members_parallel() -> Result<(), Box<dyn std::error::Error>> {
+        let mut stream = Vec::new();
+        for i in 0..16u8 {
+            let member: Vec<u8> = (0..1000u32).map(|j| (j % (i as u32 + 2)) as u8).collect();
+            compress(&member, &mut stream)?;
+            stream.extend_from_slice(&END_OF_MEMBER);
+        }
+        stream.extend_from_slice(&[0x02, b'a', b'b', b'c']);
+
+        let members = decompress_members_parallel(&stream)?;
+        assert_eq!(members.len(), 17);
+        assert_eq!(members, decompress_members(Cursor::new(&stream))?);
+        assert!(decompress_members_parallel(&[])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_member_ranges() -> Result<(), DecompressError> {
+        let stream = [0x02, b'a', b'b', b'c', 0xFF, 0xFF, 0xFF, 0x00, b'd'];
+        assert_eq!(member_ranges(&stream)?, vec![0..4, 7..9]);
+
+        // The second member cannot reference the first, and a truncated token fails the scan.
+        assert!(matches!(
+            decompress_members_parallel(&[0x02, b'a', b'b', b'c', 0xFF, 0xFF, 0xFF, 0x20, 0x02]),
+            Err(DecompressError::OffsetTooLarge { .. })
+        ));
+        assert!(matches!(
+            member_ranges(&[0x02, b'a']),
+            Err(DecompressError::UnexpectedEof {
+                expected: 3,
+                got: 1
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::decompress::{apply_token_at, check_offset, get_control_bytes};
+use crate::source::{CountingSource, SliceSource};
+use crate::{DecompressError, Offset};
+
+/// The output of `Decompress::from_slice_recovering()`, with the input it had to skip.
+#[derive(Debug)]
+pub struct Recovered {
+    /// Everything decoded, with nothing in place of the skipped input.
+    pub output: Vec<u8>,
+    /// Runs of input skipped to resynchronise, in order.
+    pub skipped: Vec<Skipped>,
+}
+
+/// A run of compressed input that could not be decoded.
+#[derive(Debug)]
+pub struct Skipped {
+    /// Positions of the skipped bytes in the compressed input.
+    pub range: Range<usize>,
+    /// The error that started the run, from the token at `range.start`.
+    pub error: DecompressError,
+}
+
+/// Decode `input` onto `dictionary`, skipping a byte and trying again wherever a token fails.
+///
+/// Dictionary tokens may only reach back as far as index `base`. Each failed token is undone, the
+/// input is advanced by one byte, and decoding resumes as if a token started there; consecutive
+/// skipped bytes are merged into one run. Exceeding `max_output` is not recovered from.
+pub(crate) fn decode_recovering(
+    input: &[u8],
+    dictionary: &mut Vec<u8>,
+    base: usize,
+    max_output: usize,
+) -> Result<Vec<Skipped>, DecompressError> {
+    let mut skipped: Vec<Skipped> = Vec::new();
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let mut source = CountingSource::new(SliceSource::new(&input[pos..]));
+        let len = dictionary.len();
+
+        match decode_token(&mut source, dictionary, base, max_output, pos) {
+            Ok(()) => pos += source.count(),
+            Err(err @ DecompressError::OutputLimitExceeded { .. }) => return Err(err),
+            Err(error) => {
+                dictionary.truncate(len);
+                match skipped.last_mut() {
+                    Some(last) if last.range.end == pos => last.range.end += 1,
+                    _ => skipped.push(Skipped {
+                        range: pos..pos + 1,
+                        error,
+                    }),
+                }
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(skipped)
+}
+
+/// Decode the single token at the start of `source`, whose control byte is at `input_pos`.
+fn decode_token(
+    source: &mut CountingSource<SliceSource>,
+    dictionary: &mut Vec<u8>,
+    base: usize,
+    max_output: usize,
+    input_pos: usize,
+) -> Result<(), DecompressError> {
+    let Some(offset) = get_control_bytes(source)? else {
+        return Ok(());
+    };
+
+    if let Offset::Dictionary { offset, .. } = offset {
+        check_offset(dictionary.len() - base, offset, input_pos)?;
+    }
+    if dictionary.len().saturating_add(offset.length()) > max_output {
+        return Err(DecompressError::OutputLimitExceeded { limit: max_output });
+    }
+    apply_token_at(source, dictionary, offset, input_pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recover(input: &[u8]) -> (Vec<u8>, Vec<Skipped>) {
+        let mut output = Vec::new();
+        let skipped = decode_recovering(input, &mut output, 0, usize::MAX).unwrap();
+        (output, skipped)
+    }
+
+    #[test]
+    fn test_recover_skips_bad_token() {
+        // "abc", a copy from 17 back, then "de" and a copy of it. Read as a token, the bad copy's
+        // offset byte is a literal running past the end, so both bytes are skipped.
+        let (output, skipped) = recover(&[
+            0x02, b'a', b'b', b'c', 0x20, 0x10, 0x01, b'd', b'e', 0x20, 0x01,
+        ]);
+        assert_eq!(output, b"abcdeded");
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].range, 4..6);
+        assert!(matches!(
+            skipped[0].error,
+            DecompressError::OffsetTooLarge {
+                offset: 17,
+                input_pos: 4,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_recover_merges_runs() {
+        // Two bad copies back to back, then a literal cut short at the end.
+        let (output, skipped) = recover(&[0x00, b'a', 0x20, 0x05, 0x20, 0x05, 0x02, b'x']);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].range, 2..8);
+        assert_eq!(output, b"a");
+    }
+}
@@ -0,0 +1,271 @@
+#[cfg(feature = "std")]
+use std::io::{BufRead, Read};
+
+use crate::DecompressError;
+
+/// A source of compressed bytes for the decoder.
+///
+/// Every decoding entry point is written once against this trait, with adapters for slices,
+/// iterators and, with the `std` feature, any `Read`. Implement it to feed the decoder from
+/// somewhere else, such as a device FIFO on a target without `std`, and decode with
+/// `Decompress::read_source()`.
+pub trait ByteSource {
+    /// Read the next byte, returning `None` if the source is exhausted.
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError>;
+
+    /// Fill `buf` completely, failing if the source runs out first.
+    ///
+    /// A source that runs out returns `DecompressError::UnexpectedEof` with the number of bytes it
+    /// managed to read.
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError>;
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ByteSource for R {
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+        let mut buf = [0u8; 1];
+        loop {
+            match self.read(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+        let mut got = 0;
+        while got < buf.len() {
+            match self.read(&mut buf[got..]) {
+                Ok(0) => {
+                    return Err(DecompressError::UnexpectedEof {
+                        expected: buf.len(),
+                        got,
+                    })
+                }
+                Ok(n) => got += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A byte source that reads straight out of a `BufRead`'s internal buffer.
+///
+/// The blanket `Read` implementation issues a `read()` call per control byte, which is a syscall
+/// for an unbuffered `File`. This only goes back to the reader when its buffer runs dry.
+#[cfg(feature = "std")]
+pub(crate) struct BufSource<R>(R);
+
+#[cfg(feature = "std")]
+impl<R: BufRead> BufSource<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        BufSource(reader)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> ByteSource for BufSource<R> {
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+        let byte = loop {
+            match self.0.fill_buf() {
+                Ok(buf) => break buf.first().copied(),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        };
+        if byte.is_some() {
+            self.0.consume(1);
+        }
+        Ok(byte)
+    }
+
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+        let mut got = 0;
+        while got < buf.len() {
+            let available = match self.0.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if available.is_empty() {
+                return Err(DecompressError::UnexpectedEof {
+                    expected: buf.len(),
+                    got,
+                });
+            }
+            let len = available.len().min(buf.len() - got);
+            buf[got..got + len].copy_from_slice(&available[..len]);
+            self.0.consume(len);
+            got += len;
+        }
+        Ok(())
+    }
+}
+
+/// An in-memory byte source that advances an index over a slice.
+pub(crate) struct SliceSource<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceSource<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        SliceSource { input, pos: 0 }
+    }
+}
+
+impl ByteSource for SliceSource<'_> {
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+        let byte = self.input.get(self.pos).copied();
+        if byte.is_some() {
+            self.pos += 1;
+        }
+        Ok(byte)
+    }
+
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+        let end = self.pos + buf.len();
+        if end > self.input.len() {
+            let got = self.input.len() - self.pos;
+            self.pos = self.input.len();
+            return Err(DecompressError::UnexpectedEof {
+                expected: buf.len(),
+                got,
+            });
+        }
+        buf.copy_from_slice(&self.input[self.pos..end]);
+        self.pos = end;
+        Ok(())
+    }
+}
+
+/// A byte source pulling bytes one at a time from an iterator.
+pub(crate) struct IterSource<I>(I);
+
+impl<I: Iterator<Item = u8>> IterSource<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        IterSource(iter)
+    }
+}
+
+impl<I: Iterator<Item = u8>> ByteSource for IterSource<I> {
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+        Ok(self.0.next())
+    }
+
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+        let expected = buf.len();
+        for (got, slot) in buf.iter_mut().enumerate() {
+            *slot = self
+                .0
+                .next()
+                .ok_or(DecompressError::UnexpectedEof { expected, got })?;
+        }
+        Ok(())
+    }
+}
+
+/// A byte source that counts how many bytes have been read through it.
+pub(crate) struct CountingSource<S> {
+    inner: S,
+    count: usize,
+}
+
+impl<S: ByteSource> CountingSource<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        CountingSource { inner, count: 0 }
+    }
+
+    /// Number of bytes successfully read so far.
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Unwrap the underlying source.
+    #[cfg(feature = "std")]
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: ByteSource> ByteSource for CountingSource<S> {
+    fn next_byte(&mut self) -> Result<Option<u8>, DecompressError> {
+        let byte = self.inner.next_byte()?;
+        if byte.is_some() {
+            self.count += 1;
+        }
+        Ok(byte)
+    }
+
+    fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), DecompressError> {
+        self.inner.read_exact_into(buf)?;
+        self.count += buf.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check an adapter over the bytes `[1, 2, 3, 4]` reads them and reports running out.
+    fn check_source(mut source: impl ByteSource) -> Result<(), DecompressError> {
+        let mut buf = [0u8; 2];
+
+        assert_eq!(source.next_byte()?, Some(1));
+        source.read_exact_into(&mut buf)?;
+        assert_eq!(buf, [2, 3]);
+        assert!(matches!(
+            source.read_exact_into(&mut buf),
+            Err(DecompressError::UnexpectedEof {
+                expected: 2,
+                got: 1
+            })
+        ));
+        assert_eq!(source.next_byte()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slice_source() -> Result<(), DecompressError> {
+        check_source(SliceSource::new(&[1, 2, 3, 4]))
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_buf_source() -> Result<(), DecompressError> {
+        check_source(BufSource::new(std::io::BufReader::with_capacity(
+            2,
+            &[1, 2, 3, 4][..],
+        )))
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_read_source() -> Result<(), DecompressError> {
+        check_source(std::io::Cursor::new([1, 2, 3, 4]))
+    }
+
+    #[test]
+    fn test_iter_source() -> Result<(), DecompressError> {
+        check_source(IterSource::new([1, 2, 3, 4].into_iter()))
+    }
+
+    #[test]
+    fn test_counting_source() -> Result<(), DecompressError> {
+        let mut source = CountingSource::new(SliceSource::new(&[1, 2, 3, 4]));
+        let mut buf = [0u8; 2];
+
+        source.next_byte()?;
+        source.read_exact_into(&mut buf)?;
+        assert_eq!(source.count(), 3);
+        source.next_byte()?;
+        source.next_byte()?;
+        assert_eq!(source.count(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use core::iter::Sum;
+use core::ops::{Add, AddAssign};
+
+use crate::Offset;
+
+/// Counters describing how a stream decompressed, returned by `decompress_with_stats()`.
+///
+/// Useful for auditing an encoder: a stream made mostly of literal bytes found few matches.
+///
+/// Stats from several streams can be added together, or summed from an iterator, to give the
+/// counters for all of them at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecompressStats {
+    /// Number of literal tokens.
+    pub literal_tokens: usize,
+    /// Number of dictionary tokens.
+    pub dictionary_tokens: usize,
+    /// Output bytes copied verbatim from literal tokens.
+    pub literal_bytes: usize,
+    /// Output bytes copied from earlier output by dictionary tokens.
+    pub copied_bytes: usize,
+    /// Compressed bytes read, including every control and follow-up byte.
+    pub compressed_bytes: usize,
+}
+
+impl DecompressStats {
+    /// Total decompressed length, `literal_bytes + copied_bytes`.
+    pub fn decompressed_bytes(&self) -> usize {
+        self.literal_bytes + self.copied_bytes
+    }
+
+    /// Compression ratio, as decompressed bytes per compressed byte.
+    ///
+    /// Returns `0.0` for an empty stream.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            return 0.0;
+        }
+        self.decompressed_bytes() as f64 / self.compressed_bytes as f64
+    }
+
+    /// Count a token that has been applied.
+    pub(crate) fn record(&mut self, offset: Offset) {
+        match offset {
+            Offset::Literal { length } => {
+                self.literal_tokens += 1;
+                self.literal_bytes += length;
+            }
+            Offset::Dictionary { length, .. } => {
+                self.dictionary_tokens += 1;
+                self.copied_bytes += length;
+            }
+            Offset::EndOfMember => {}
+        }
+    }
+}
+
+impl AddAssign for DecompressStats {
+    fn add_assign(&mut self, other: Self) {
+        self.literal_tokens += other.literal_tokens;
+        self.dictionary_tokens += other.dictionary_tokens;
+        self.literal_bytes += other.literal_bytes;
+        self.copied_bytes += other.copied_bytes;
+        self.compressed_bytes += other.compressed_bytes;
+    }
+}
+
+impl Add for DecompressStats {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl Sum for DecompressStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = DecompressStats::default();
+        assert_eq!(stats.ratio(), 0.0);
+
+        stats.record(Offset::Literal { length: 3 });
+        stats.record(Offset::Dictionary {
+            length: 9,
+            offset: 3,
+        });
+        stats.record(Offset::EndOfMember);
+        stats.compressed_bytes = 6;
+
+        assert_eq!(stats.literal_tokens, 1);
+        assert_eq!(stats.dictionary_tokens, 1);
+        assert_eq!(stats.decompressed_bytes(), 12);
+        assert_eq!(stats.ratio(), 2.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sum() -> Result<(), crate::DecompressError> {
+        use std::io::Cursor;
+
+        // "abc" then a copy of 5 bytes from 3 back: 8 bytes out of 6 in.
+        let stream = [0x02, b'a', b'b', b'c', 0x60, 0x02];
+        let (_, stats) = crate::decompress_with_stats(Cursor::new(stream))?;
+        assert_eq!(stats.ratio(), 8.0 / 6.0);
+
+        let (_, other) = crate::decompress_with_stats(Cursor::new([0x00, b'x']))?;
+        let total: DecompressStats = [stats, other].into_iter().sum();
+        assert_eq!(total, stats + other);
+        assert_eq!(
+            total,
+            DecompressStats {
+                literal_tokens: 2,
+                dictionary_tokens: 1,
+                literal_bytes: 4,
+                copied_bytes: 5,
+                compressed_bytes: 8,
+            }
+        );
+        assert_eq!(total.ratio(), 9.0 / 8.0);
+        Ok(())
+    }
+}
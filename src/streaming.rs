@@ -0,0 +1,251 @@
+use alloc::vec::Vec;
+
+use crate::decompress::{follow_len, get_control_bytes, parse_token};
+use crate::source::SliceSource;
+use crate::window::Window;
+use crate::{DecompressError, Offset, MAX_LITERAL_LEN, MAX_OFFSET};
+
+/// Longest encoding of a single token: a control byte and a full literal.
+const MAX_TOKEN_LEN: usize = 1 + MAX_LITERAL_LEN;
+
+/// A push-style decompressor, fed compressed data in whatever chunks it arrives in.
+///
+/// Where `Decompressor` pulls from a reader, this is handed input by the caller and does no I/O of
+/// its own, suiting network protocols and event loops. Every complete token in the input is decoded
+/// straight away; a token cut off at the end of a chunk is held until the next chunk completes it.
+///
+/// Only the sliding window needed to resolve dictionary references is kept between calls.
+///
+/// ```
+/// # fn main() -> Result<(), lz77::DecompressError> {
+/// let stream = [0x02, b'a', b'b', b'c', 0x60, 0x02];
+/// let mut decoder = lz77::StreamingDecoder::new();
+/// let mut output = Vec::new();
+/// for chunk in stream.chunks(4) {
+///     decoder.decode(chunk, &mut output)?;
+/// }
+/// decoder.finish()?;
+/// assert_eq!(output, b"abcabcab");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct StreamingDecoder {
+    /// Recently decoded bytes, used as the dictionary for upcoming tokens.
+    window: Window<MAX_OFFSET>,
+    /// The start of a token cut off at the end of the previous chunk.
+    pending: Vec<u8>,
+    /// Compressed bytes consumed so far, for the positions reported in errors.
+    input_pos: usize,
+}
+
+impl Default for StreamingDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingDecoder {
+    /// Create a decoder at the start of a stream.
+    pub fn new() -> Self {
+        StreamingDecoder {
+            window: Window::new(),
+            pending: Vec::new(),
+            input_pos: 0,
+        }
+    }
+
+    /// Decode the next chunk of compressed data, appending its output to `output`.
+    ///
+    /// Every complete token is decoded, and a partial token at the end of `input` is buffered, so
+    /// the whole of `input` is always consumed and the caller has nothing to hold on to.
+    ///
+    /// After an error the decoder is left part way through a token and should be discarded.
+    pub fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), DecompressError> {
+        let mut output = |bytes: &[u8]| {
+            output.extend_from_slice(bytes);
+            Ok(())
+        };
+        // Tokens decoded before an error are still handed over.
+        let result = self.decode_tokens(input, &mut output);
+        self.window.flush(&mut output)?;
+        result
+    }
+
+    /// Decode every complete token of `input`, buffering a partial one at the end.
+    fn decode_tokens<F>(&mut self, input: &[u8], output: &mut F) -> Result<(), DecompressError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        let mut rest = input;
+
+        if !self.pending.is_empty() {
+            while let Some(&byte) = rest.first() {
+                self.pending.push(byte);
+                rest = &rest[1..];
+                if token_len(&self.pending)?.is_some() {
+                    // Taken out for the call, then put back to keep its allocation.
+                    let mut token = core::mem::take(&mut self.pending);
+                    self.apply(&token, output)?;
+                    token.clear();
+                    self.pending = token;
+                    break;
+                }
+            }
+        }
+
+        while let Some(len) = token_len(rest)? {
+            let (token, tail) = rest.split_at(len);
+            self.apply(token, output)?;
+            rest = tail;
+        }
+        self.pending.extend_from_slice(rest);
+
+        Ok(())
+    }
+
+    /// Check that the stream ended on a token boundary.
+    ///
+    /// Fails with `DecompressError::UnexpectedEof` if input was buffered for a token that never
+    /// completed, counting the follow-up bytes or literal data it was cut short in, as `decode()`
+    /// does.
+    pub fn finish(self) -> Result<(), DecompressError> {
+        let Some(&cb) = self.pending.first() else {
+            return Ok(());
+        };
+
+        let header = 1 + follow_len(cb);
+        let part = if self.pending.len() < header {
+            1..header
+        } else {
+            header..required_len(&self.pending)?
+        };
+        Err(DecompressError::UnexpectedEof {
+            expected: part.len(),
+            got: self.pending.len() - part.start,
+        })
+    }
+
+    /// Decode one complete token into the window, which hands its output on to `output`.
+    fn apply<F>(&mut self, token: &[u8], output: &mut F) -> Result<(), DecompressError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        let mut source = SliceSource::new(token);
+        if let Some(offset) = get_control_bytes(&mut source)? {
+            self.window
+                .apply_token(&mut source, offset, self.input_pos, output)?;
+        }
+        self.input_pos += token.len();
+        Ok(())
+    }
+}
+
+/// Length of the token at the start of `bytes`, or `None` if `bytes` does not hold all of it.
+fn token_len(bytes: &[u8]) -> Result<Option<usize>, DecompressError> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    let len = required_len(bytes)?;
+    Ok((bytes.len() >= len).then_some(len))
+}
+
+/// Length of the non-empty token at the start of `bytes`, as far as can be told from `bytes`.
+///
+/// Until its follow-up bytes have arrived that is only the length of the control bytes, which for
+/// a literal are followed by its data.
+fn required_len(bytes: &[u8]) -> Result<usize, DecompressError> {
+    let cb = bytes[0];
+    let header = 1 + follow_len(cb);
+    let Some(follow) = bytes.get(1..header) else {
+        return Ok(header);
+    };
+
+    let len = match parse_token(cb, follow)? {
+        Offset::Literal { length } => header + length,
+        _ => header,
+    };
+    debug_assert!(len <= MAX_TOKEN_LEN);
+    Ok(len)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaming_decoder_every_split() -> Result<(), DecompressError> {
+        let compressed = include_bytes!("../tests/data/000.compressed");
+        let expected = include_bytes!("../tests/data/000.decompressed");
+
+        for split in 0..=compressed.len() {
+            let mut decoder = StreamingDecoder::new();
+            let mut output = Vec::new();
+            let (head, tail) = compressed.split_at(split);
+            decoder.decode(head, &mut output)?;
+            decoder.decode(tail, &mut output)?;
+            decoder.finish()?;
+            assert!(output == expected, "split at {}", split);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_decoder_byte_at_a_time() -> Result<(), DecompressError> {
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i * i % 251) as u8).collect();
+        let mut compressed = Vec::new();
+        crate::compress(&data, &mut compressed)?;
+
+        let mut decoder = StreamingDecoder::new();
+        let mut output = Vec::new();
+        for byte in compressed.chunks(1) {
+            decoder.decode(byte, &mut output)?;
+            assert!(decoder.pending.len() < MAX_TOKEN_LEN);
+        }
+        decoder.finish()?;
+        assert_eq!(output, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_decoder_truncated() -> Result<(), DecompressError> {
+        let mut decoder = StreamingDecoder::new();
+        let mut output = Vec::new();
+        decoder.decode(&[0x02, b'a', b'b', b'c', 0x04, b'x'], &mut output)?;
+        assert_eq!(output, b"abc");
+        assert!(matches!(
+            decoder.finish(),
+            Err(DecompressError::UnexpectedEof {
+                expected: 5,
+                got: 1
+            })
+        ));
+
+        // The same counts as decoding the truncated stream in one go.
+        for truncated in [
+            &[0x1F, 1, 2][..],
+            &[0x00, b'a', 0xE0, 0x00],
+            &[0x00, b'a', 0x20],
+        ] {
+            let mut decoder = StreamingDecoder::new();
+            decoder.decode(truncated, &mut output)?;
+            assert_eq!(
+                format!("{:?}", decoder.finish()),
+                format!("{:?}", crate::decompress_slice(truncated)),
+            );
+        }
+
+        // Errors from the tokens themselves report where in the whole stream they were.
+        let mut decoder = StreamingDecoder::new();
+        decoder.decode(&[0x02, b'a', b'b'], &mut output)?;
+        assert!(matches!(
+            decoder.decode(&[b'c', 0x20, 0x09], &mut output),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 10,
+                dictionary_len: 3,
+                input_pos: 4
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! Deterministic test data for the unit tests.
+
+/// Xorshift generator, so every input is reproducible from its seed.
+pub(crate) fn xorshift(seed: u32) -> impl FnMut() -> u32 {
+    let mut state = seed;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    }
+}
+
+/// `len` bytes of incompressible noise from a xorshift generator started at `seed`.
+pub(crate) fn noise(seed: u32, len: usize) -> Vec<u8> {
+    let mut next = xorshift(seed);
+    (0..len).map(|_| next() as u8).collect()
+}
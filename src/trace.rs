@@ -0,0 +1,69 @@
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{DecompressError, Offset};
+
+/// Number of tokens kept by `Decompress::trace()`.
+pub const TRACE_LEN: usize = 16;
+
+/// A decoded token recorded by `Decompress::trace()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceEntry {
+    /// Index in the compressed stream of the token's control byte.
+    pub input_pos: usize,
+    /// The token itself.
+    pub offset: Offset,
+    /// Length of the decompressed output once the token was applied.
+    pub output_len: usize,
+}
+
+/// The most recent `TRACE_LEN` tokens applied by the decoder.
+#[derive(Debug, Default)]
+pub(crate) struct Trace {
+    entries: VecDeque<TraceEntry>,
+}
+
+impl Trace {
+    pub(crate) fn record(&mut self, entry: TraceEntry) {
+        if self.entries.len() == TRACE_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Wrap `error` in `DecompressError::Traced`, with the recorded tokens oldest first.
+    pub(crate) fn attach(self, error: DecompressError) -> DecompressError {
+        DecompressError::Traced {
+            error: Box::new(error),
+            trace: Vec::from(self.entries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_keeps_latest() {
+        let mut trace = Trace::default();
+        for i in 0..TRACE_LEN + 3 {
+            trace.record(TraceEntry {
+                input_pos: i,
+                offset: Offset::Literal { length: 1 },
+                output_len: i + 1,
+            });
+        }
+
+        let DecompressError::Traced { error, trace } = trace.attach(DecompressError::ZeroOffset)
+        else {
+            panic!("expected a traced error");
+        };
+        assert!(matches!(*error, DecompressError::ZeroOffset));
+        assert_eq!(trace.len(), TRACE_LEN);
+        assert_eq!(trace[0].input_pos, 3);
+        assert_eq!(trace[TRACE_LEN - 1].input_pos, TRACE_LEN + 2);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::decompress::check_offset;
+use crate::source::ByteSource;
+use crate::{DecompressError, Offset, MAX_LITERAL_LEN};
+
+/// A sliding window of the last `W` decoded bytes, held in a ring buffer.
+///
+/// Bytes are pushed in one at a time, and handed to an output callback in batches of at most `W`
+/// bytes as the buffer wraps, or when `flush()` is called. Dictionary offsets are resolved against
+/// the bytes still held, so a reference further back than that fails rather than reading stale
+/// data.
+#[derive(Debug)]
+pub(crate) struct Window<const W: usize> {
+    buf: [u8; W],
+    /// Index in `buf` the next byte is written to.
+    pos: usize,
+    /// Number of valid bytes of history in `buf`, at most `W`.
+    filled: usize,
+    /// Index in `buf` of the first byte not yet handed to the output.
+    flushed: usize,
+}
+
+impl<const W: usize> Window<W> {
+    pub(crate) const fn new() -> Self {
+        const { assert!(W > 0, "window must not be empty") };
+        Window {
+            buf: [0; W],
+            pos: 0,
+            filled: 0,
+            flushed: 0,
+        }
+    }
+
+    /// Forget all history, without handing unflushed bytes to the output.
+    pub(crate) fn clear(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+        self.flushed = 0;
+    }
+
+    /// Index in `buf` of the byte `offset` bytes back from the end of the history.
+    ///
+    /// Fails with `DecompressError::OffsetTooLarge` if the window no longer holds it, reporting
+    /// `input_pos` as the position of the token's control byte.
+    pub(crate) fn resolve(
+        &self,
+        offset: usize,
+        input_pos: usize,
+    ) -> Result<usize, DecompressError> {
+        check_offset(self.filled, offset, input_pos)?;
+        Ok((self.pos + W - offset) % W)
+    }
+
+    /// Append a byte, first handing the unflushed bytes to `output` if the buffer wraps.
+    pub(crate) fn push<F>(&mut self, byte: u8, output: &mut F) -> Result<(), DecompressError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        self.buf[self.pos] = byte;
+        self.pos += 1;
+        self.filled = (self.filled + 1).min(W);
+
+        if self.pos == W {
+            output(&self.buf[self.flushed..])?;
+            self.pos = 0;
+            self.flushed = 0;
+        }
+        Ok(())
+    }
+
+    /// Append `length` bytes copied from `offset` bytes back.
+    ///
+    /// The source index is resolved again for every byte, so a copy overlapping the bytes it
+    /// produces repeats the pattern as the format requires.
+    pub(crate) fn copy_match<F>(
+        &mut self,
+        length: usize,
+        offset: usize,
+        input_pos: usize,
+        output: &mut F,
+    ) -> Result<(), DecompressError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        self.resolve(offset, input_pos)?;
+        for _ in 0..length {
+            let byte = self.buf[(self.pos + W - offset) % W];
+            self.push(byte, output)?;
+        }
+        Ok(())
+    }
+
+    /// Apply a single parsed token, reading a literal's data from `source`.
+    ///
+    /// As with `apply_token_at()`, a token that would produce no output is rejected, and
+    /// `input_pos` is the position of the token's control byte, reported in errors.
+    pub(crate) fn apply_token<S, F>(
+        &mut self,
+        source: &mut S,
+        offset: Offset,
+        input_pos: usize,
+        output: &mut F,
+    ) -> Result<(), DecompressError>
+    where
+        S: ByteSource,
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        match offset {
+            Offset::Literal { length: 0 } | Offset::Dictionary { length: 0, .. } => {
+                return Err(DecompressError::ZeroLength);
+            }
+            Offset::Literal { length } => {
+                let mut literal = [0u8; MAX_LITERAL_LEN];
+                source.read_exact_into(&mut literal[..length])?;
+                for &byte in &literal[..length] {
+                    self.push(byte, output)?;
+                }
+            }
+            Offset::Dictionary { length, offset } => {
+                self.copy_match(length, offset, input_pos, output)?;
+            }
+            Offset::EndOfMember => {}
+        }
+        Ok(())
+    }
+
+    /// Hand every byte not yet passed to `output` over.
+    pub(crate) fn flush<F>(&mut self, output: &mut F) -> Result<(), DecompressError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DecompressError>,
+    {
+        if self.flushed < self.pos {
+            output(&self.buf[self.flushed..self.pos])?;
+            self.flushed = self.pos;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// Collects output batches, recording their sizes.
+    #[derive(Default)]
+    struct Sink {
+        bytes: Vec<u8>,
+        batches: Vec<usize>,
+    }
+
+    impl Sink {
+        fn output(&mut self) -> impl FnMut(&[u8]) -> Result<(), DecompressError> + '_ {
+            |batch| {
+                self.bytes.extend_from_slice(batch);
+                self.batches.push(batch.len());
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_resolve() -> Result<(), DecompressError> {
+        let mut window = Window::<4>::new();
+        let mut sink = Sink::default();
+
+        assert!(matches!(
+            window.resolve(1, 5),
+            Err(DecompressError::OffsetTooLarge {
+                offset: 1,
+                dictionary_len: 0,
+                input_pos: 5
+            })
+        ));
+        assert!(matches!(
+            window.resolve(0, 0),
+            Err(DecompressError::ZeroOffset)
+        ));
+
+        for byte in [1, 2, 3] {
+            window.push(byte, &mut sink.output())?;
+        }
+        assert_eq!(window.resolve(1, 0)?, 2);
+        assert_eq!(window.resolve(3, 0)?, 0);
+        assert!(window.resolve(4, 0).is_err());
+
+        // After wrapping, offsets resolve across the end of the buffer, up to the whole window.
+        for byte in [4, 5] {
+            window.push(byte, &mut sink.output())?;
+        }
+        assert_eq!(window.resolve(1, 0)?, 0);
+        assert_eq!(window.resolve(2, 0)?, 3);
+        assert_eq!(window.resolve(4, 0)?, 1);
+        assert!(window.resolve(5, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_copy_match() -> Result<(), DecompressError> {
+        let mut window = Window::<8>::new();
+        let mut sink = Sink::default();
+
+        for &byte in b"abc" {
+            window.push(byte, &mut sink.output())?;
+        }
+        window.copy_match(2, 3, 0, &mut sink.output())?;
+        // Overlapping, and wrapping around the end of the buffer part way through.
+        window.copy_match(7, 2, 0, &mut sink.output())?;
+        window.copy_match(3, 8, 0, &mut sink.output())?;
+        window.flush(&mut sink.output())?;
+
+        assert_eq!(sink.bytes, [&b"abcab"[..], b"abababa", b"bab"].concat());
+        assert_eq!(sink.batches, [8, 7]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_apply_token() -> Result<(), DecompressError> {
+        let mut window = Window::<8>::new();
+        let mut sink = Sink::default();
+        let mut source = crate::source::SliceSource::new(b"abcd");
+
+        window.apply_token(
+            &mut source,
+            Offset::Literal { length: 3 },
+            0,
+            &mut sink.output(),
+        )?;
+        window.apply_token(
+            &mut source,
+            Offset::Dictionary {
+                length: 4,
+                offset: 2,
+            },
+            4,
+            &mut sink.output(),
+        )?;
+        window.flush(&mut sink.output())?;
+        assert_eq!(sink.bytes, b"abcbcbc");
+
+        assert!(matches!(
+            window.apply_token(
+                &mut source,
+                Offset::Literal { length: 2 },
+                6,
+                &mut sink.output()
+            ),
+            Err(DecompressError::UnexpectedEof {
+                expected: 2,
+                got: 1
+            })
+        ));
+        assert!(matches!(
+            window.apply_token(
+                &mut source,
+                Offset::Dictionary {
+                    length: 0,
+                    offset: 1
+                },
+                6,
+                &mut sink.output()
+            ),
+            Err(DecompressError::ZeroLength)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_flush_batches() -> Result<(), DecompressError> {
+        let mut window = Window::<4>::new();
+        let mut sink = Sink::default();
+
+        for byte in 0..10 {
+            window.push(byte, &mut sink.output())?;
+            if byte == 1 {
+                window.flush(&mut sink.output())?;
+            }
+        }
+        window.flush(&mut sink.output())?;
+        window.flush(&mut sink.output())?;
+
+        assert_eq!(sink.bytes, (0..10).collect::<Vec<_>>());
+        assert_eq!(sink.batches, [2, 2, 4, 2]);
+
+        window.clear();
+        assert!(window.resolve(1, 0).is_err());
+        Ok(())
+    }
+}
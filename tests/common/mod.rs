@@ -0,0 +1,47 @@
+//! Deterministic test data, shared by the integration tests and benchmarks.
+
+// Each includer uses only some of these.
+#![allow(dead_code)]
+
+/// Xorshift generator, so every input is reproducible from its seed.
+pub fn xorshift(seed: u32) -> impl FnMut() -> u32 {
+    let mut state = seed;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    }
+}
+
+/// `len` bytes of incompressible noise from a xorshift generator started at `seed`.
+pub fn noise(seed: u32, len: usize) -> Vec<u8> {
+    let mut next = xorshift(seed);
+    (0..len).map(|_| next() as u8).collect()
+}
+
+/// At least `len` bytes of text-like data drawn from a small vocabulary, with the occasional stray
+/// byte, from a xorshift generator started at `seed`.
+pub fn text(seed: u32, len: usize) -> Vec<u8> {
+    let words = [
+        "lorem",
+        "ipsum",
+        "dolor",
+        "sit",
+        "amet",
+        "consectetur",
+        "adipiscing",
+    ];
+    let mut data = Vec::new();
+    let mut next = xorshift(seed);
+    while data.len() < len {
+        let seed = next();
+        data.extend_from_slice(words[seed as usize % words.len()].as_bytes());
+        data.push(if seed.is_multiple_of(7) {
+            (seed >> 8) as u8
+        } else {
+            b' '
+        });
+    }
+    data
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "std")]
+
+//! Minimized inputs for bugs the decoder has had, or that the fuzz target looks for, kept as
+//! bytes in source. Each runs through both `decompress` and `decompress_slice`.
+
+use std::io::Cursor;
+
+use lz77::DecompressError;
+
+enum Expect {
+    Output(&'static [u8]),
+    Error(fn(&DecompressError) -> bool),
+}
+
+const CORPUS: &[(&str, &[u8], Expect)] = &[
+    // Dictionary copies before any output, which once underflowed computing the copy's start.
+    (
+        "copy_from_empty",
+        &[0x20, 0x00],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::OffsetTooLarge {
+                    offset: 1,
+                    dictionary_len: 0,
+                    input_pos: 0
+                }
+            )
+        }),
+    ),
+    (
+        "copy_past_start",
+        &[0x00, b'a', 0x20, 0x01],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::OffsetTooLarge {
+                    offset: 2,
+                    dictionary_len: 1,
+                    input_pos: 2
+                }
+            )
+        }),
+    ),
+    (
+        "max_copy_from_empty",
+        &[0xFF, 0xFF, 0xFF],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::OffsetTooLarge {
+                    offset: lz77::MAX_OFFSET,
+                    ..
+                }
+            )
+        }),
+    ),
+    // A long copy that must be rejected before its output is allocated.
+    (
+        "long_copy_past_start",
+        &[0x01, 0xAB, 0xCD, 0xE0, 0xFF, 0x02],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::OffsetTooLarge {
+                    offset: 3,
+                    dictionary_len: 2,
+                    input_pos: 3
+                }
+            )
+        }),
+    ),
+    // Streams cut off inside a token.
+    (
+        "truncated_copy",
+        &[0x00, b'a', 0x20],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::UnexpectedEof {
+                    expected: 1,
+                    got: 0
+                }
+            )
+        }),
+    ),
+    (
+        "truncated_long_copy",
+        &[0x00, b'a', 0xE0, 0x00],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::UnexpectedEof {
+                    expected: 2,
+                    got: 1
+                }
+            )
+        }),
+    ),
+    (
+        "truncated_literal",
+        &[0x1F, 1, 2],
+        Expect::Error(|err| {
+            matches!(
+                err,
+                DecompressError::UnexpectedEof {
+                    expected: 32,
+                    got: 2
+                }
+            )
+        }),
+    ),
+    // Copies overlapping their own output, and reaching back to the very first byte.
+    (
+        "run_of_one_byte",
+        &[0x00, b'A', 0xE0, 0x00, 0x00],
+        Expect::Output(b"AAAAAAAAAA"),
+    ),
+    (
+        "overlapping_pattern",
+        &[0x01, b'a', b'b', 0x40, 0x01],
+        Expect::Output(b"ababab"),
+    ),
+    (
+        "copy_whole_output",
+        &[0x02, b'a', b'b', b'c', 0x20, 0x02],
+        Expect::Output(b"abcabc"),
+    ),
+    ("empty", &[], Expect::Output(b"")),
+];
+
+#[test]
+fn corpus() {
+    for (name, input, expect) in CORPUS {
+        for result in [
+            lz77::decompress(Cursor::new(input)),
+            lz77::decompress_slice(input),
+        ] {
+            match (expect, result) {
+                (Expect::Output(expected), Ok(output)) => assert_eq!(output, *expected, "{}", name),
+                (Expect::Error(check), Err(err)) => assert!(check(&err), "{}: {:?}", name, err),
+                (_, result) => panic!("{}: unexpected {:?}", name, result),
+            }
+        }
+    }
+}
+
+/// Every control byte once panicked in `cb_mask` if it matched no tier. None may panic now, alone
+/// or followed by more bytes, and both decoding paths must agree.
+#[test]
+fn every_control_byte() {
+    for cb in 0..=u8::MAX {
+        for input in [vec![cb], vec![0x00, b'x', cb, 0x00, 0x00]] {
+            let from_reader = lz77::decompress(Cursor::new(&input));
+            let from_slice = lz77::decompress_slice(&input);
+            match (from_reader, from_slice) {
+                (Ok(a), Ok(b)) => assert_eq!(a, b),
+                (Err(_), Err(_)) => {}
+                (a, b) => panic!("{:02X?}: {:?} vs {:?}", input, a, b),
+            }
+        }
+    }
+}
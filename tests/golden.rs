@@ -0,0 +1,104 @@
+#![cfg(feature = "std")]
+
+use std::io::{Cursor, Read};
+
+mod common;
+
+use common::{noise, xorshift};
+
+/// Inputs with different match structure, generated rather than committed as binaries.
+fn inputs() -> Vec<(&'static str, Vec<u8>)> {
+    let noise = noise(0x2545_F491, 20_000);
+
+    let words = [
+        "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog",
+    ];
+    let mut next = xorshift(0x9E37_79B9);
+    let text = (0..4000)
+        .map(|_| words[next() as usize % words.len()])
+        .collect::<Vec<_>>()
+        .join(" ")
+        .into_bytes();
+
+    let mut runs = Vec::new();
+    let mut next = xorshift(0x1234_5678);
+    while runs.len() < 20_000 {
+        let len = next() as usize % 600 + 1;
+        runs.extend(std::iter::repeat_n(next() as u8, len));
+    }
+
+    // A block repeated just inside and just outside the window.
+    let far = [
+        &noise[..1000],
+        &[0; lz77::MAX_OFFSET - 1000][..],
+        &noise[..1000],
+    ]
+    .concat();
+    let too_far = [&noise[..1000], &[0; lz77::MAX_OFFSET][..], &noise[..1000]].concat();
+
+    vec![
+        ("empty", Vec::new()),
+        ("single", vec![0x42]),
+        ("noise", noise),
+        ("text", text),
+        ("runs", runs),
+        (
+            "sawtooth",
+            (0..50_000u32).map(|i| (i % 251) as u8).collect(),
+        ),
+        ("far", far),
+        ("too_far", too_far),
+    ]
+}
+
+#[test]
+fn generated_round_trips() {
+    for (name, data) in inputs() {
+        for level in [1, 6, 9] {
+            let mut compressed = Vec::new();
+            lz77::compress_with_options(
+                &data,
+                &mut compressed,
+                lz77::CompressOptions::level(level),
+            )
+            .unwrap();
+
+            assert_eq!(
+                lz77::decompress(Cursor::new(&compressed)).unwrap(),
+                data,
+                "{} at level {}",
+                name,
+                level
+            );
+            assert_eq!(
+                lz77::decompress_slice(&compressed).unwrap(),
+                data,
+                "{}",
+                name
+            );
+
+            let mut streamed = Vec::new();
+            lz77::Decompressor::new(Cursor::new(&compressed))
+                .read_to_end(&mut streamed)
+                .unwrap();
+            assert_eq!(streamed, data, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn committed_fixture_decodes() {
+    let compressed = std::fs::read("tests/data/000.compressed").unwrap();
+    let expected = std::fs::read("tests/data/000.decompressed").unwrap();
+
+    assert_eq!(
+        lz77::decompress(Cursor::new(&compressed)).unwrap(),
+        expected
+    );
+    assert_eq!(lz77::decompress_slice(&compressed).unwrap(), expected);
+
+    // The fixture also survives a round trip through the crate's own encoder.
+    let mut recompressed = Vec::new();
+    lz77::compress(&expected, &mut recompressed).unwrap();
+    assert_eq!(lz77::decompress_slice(&recompressed).unwrap(), expected);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "std")]
+
+use std::io::Cursor;
+
+use proptest::prelude::*;
+
+fn round_trip(data: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    lz77::compress(data, &mut compressed).unwrap();
+    lz77::decompress(Cursor::new(compressed)).unwrap()
+}
+
+/// Inputs covering the shapes the encoder treats differently.
+///
+/// Every branch shrinks towards short, simple byte vectors so failures report a minimal input.
+fn inputs() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        // Random noise, mostly emitted as literals.
+        prop::collection::vec(any::<u8>(), 0..2048),
+        // A small alphabet, producing many short matches.
+        prop::collection::vec(0..4u8, 0..2048),
+        // A short pattern repeated many times, producing long overlapping matches.
+        (prop::collection::vec(any::<u8>(), 1..16), 1..300usize)
+            .prop_map(|(pattern, count)| pattern.repeat(count)),
+        // Runs of a single byte, including lengths around the maximum match length.
+        (any::<u8>(), 0..1200usize).prop_map(|(byte, len)| vec![byte; len]),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn round_trips(data in inputs()) {
+        prop_assert_eq!(round_trip(&data), data);
+    }
+
+    #[test]
+    fn round_trips_slice(data in inputs()) {
+        let mut compressed = Vec::new();
+        lz77::compress(&data, &mut compressed).unwrap();
+        prop_assert_eq!(lz77::decompress_slice(&compressed).unwrap(), data);
+    }
+
+    /// The encoder's output is always in the canonical form the strict decoder checks for.
+    #[test]
+    fn output_is_canonical(data in inputs(), level in 1..=9u32) {
+        let mut compressed = Vec::new();
+        lz77::compress_with_options(&data, &mut compressed, lz77::CompressOptions::level(level))
+            .unwrap();
+        prop_assert_eq!(lz77::Decompress::new().canonical(true).from_slice(&compressed).unwrap(), data);
+    }
+
+    /// Every token of a stream that decodes must add output, so decoding always makes progress.
+    #[test]
+    fn tokens_make_progress(stream in prop::collection::vec(any::<u8>(), 0..512)) {
+        if let Ok((output, stats)) = lz77::decompress_with_stats(Cursor::new(&stream)) {
+            prop_assert!(output.len() >= stats.literal_tokens + stats.dictionary_tokens);
+        }
+    }
+}
+
+#[test]
+fn round_trips_edge_cases() {
+    assert_eq!(round_trip(&[]), Vec::<u8>::new());
+    assert_eq!(round_trip(&[0x42]), vec![0x42]);
+    assert_eq!(round_trip(&[0; 264]), vec![0; 264]);
+    assert_eq!(round_trip(&[0; 265]), vec![0; 265]);
+}